@@ -22,7 +22,9 @@ pub async fn run() -> errors::Result<()> {
         &config().discord_token,
         Intents::GUILDS
             | Intents::GUILD_MESSAGES
+            | Intents::GUILD_MEMBERS
             | Intents::MESSAGE_CONTENT
+            | Intents::DIRECT_MESSAGES
             | Intents::GUILD_WEBHOOKS,
         (to_minecraft, from_minecraft),
     );
@@ -43,6 +45,7 @@ pub async fn run() -> errors::Result<()> {
 pub enum Chat {
     Guild,
     Officer,
+    Party,
 }
 
 impl Chat {
@@ -50,15 +53,30 @@ impl Chat {
         match self {
             Chat::Guild => "gc",
             Chat::Officer => "oc",
+            Chat::Party => "pc",
         }
     }
-}
 
-impl From<Chat> for u64 {
-    fn from(value: Chat) -> Self {
-        match value {
-            Chat::Guild => config().channels.guild,
-            Chat::Officer => config().channels.officer,
+    /// The Discord channel the chat is bridged to, if any
+    pub fn channel(&self) -> Option<u64> {
+        let channels = &config().channels;
+
+        match self {
+            Chat::Guild => Some(channels.guild),
+            Chat::Officer => Some(channels.officer),
+            Chat::Party => channels.party,
+        }
+    }
+
+    /// The chat bridged to a Discord channel, if any
+    pub fn from_channel(id: u64) -> Option<Chat> {
+        let channels = &config().channels;
+
+        match id {
+            id if id == channels.guild => Some(Chat::Guild),
+            id if id == channels.officer => Some(Chat::Officer),
+            id if Some(id) == channels.party => Some(Chat::Party),
+            _ => None,
         }
     }
 }
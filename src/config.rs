@@ -1,17 +1,100 @@
-use once_cell::sync::OnceCell;
-use std::env::var;
+use crate::{bridge::Chat, payloads::events::Location};
+use parking_lot::{const_rwlock, RwLock};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    fmt::Display,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::Level;
 
-static CONFIG: OnceCell<Config> = OnceCell::new();
+/// The current config, swapped out whole when it is reloaded
+static CONFIG: RwLock<Option<Arc<Config>>> = const_rwlock(None);
+
+/// The config file read when `CONFIG_PATH` isn't set
+const DEFAULT_PATH: &str = "bridge.toml";
 
 pub fn init(config: Config) {
-    CONFIG
-        .set(config)
-        .map_err(|_| ()) // Delete the existant config from error logs so as not to leak any secrets
-        .expect("Config already initialized")
+    let mut current = CONFIG.write();
+    assert!(current.is_none(), "Config already initialized");
+
+    *current = Some(Arc::new(config));
+}
+
+/// Get the current config. Hold on to it only as long as needed, so a reload is picked up
+pub fn config() -> Arc<Config> {
+    CONFIG.read().clone().expect("Config not initialized")
+}
+
+/// Get the current config, or `None` if it hasn't been loaded yet
+pub fn try_config() -> Option<Arc<Config>> {
+    CONFIG.read().clone()
+}
+
+/// Load the config again, replacing the current one if the new one is valid.
+///
+/// The Discord token, Microsoft account and server can't change while the bridge is running,
+/// so changes to them are ignored until it is restarted.
+pub fn reload() -> Result<(), EnvError> {
+    let mut config = Config::load()?;
+    let current = self::config();
+
+    if config.discord_token != current.discord_token
+        || config.email != current.email
+        || config.server_address != current.server_address
+        || config.server_port != current.server_port
+    {
+        tracing::warn!(
+            "The Discord token, email and server only change when the bridge is restarted"
+        );
+
+        config.discord_token = current.discord_token.clone();
+        config.email = current.email.clone();
+        config.server_address = current.server_address.clone();
+        config.server_port = current.server_port;
+    }
+
+    *CONFIG.write() = Some(Arc::new(config));
+    tracing::info!("Reloaded the config");
+
+    Ok(())
 }
 
-pub fn config() -> &'static Config {
-    CONFIG.get().expect("Config not initialized")
+/// Initialise the config with placeholder channels, for tests which need it
+#[cfg(test)]
+pub fn init_for_tests() {
+    let mut current = CONFIG.write();
+
+    if current.is_none() {
+        let source = Source {
+            path: DEFAULT_PATH.to_string(),
+            // Anything tests save is kept out of the real data directory
+            env: HashMap::from([(
+                "DATA_DIR".to_string(),
+                std::env::temp_dir()
+                    .join("bridge-test-data")
+                    .into_os_string(),
+            )]),
+            file: toml::from_str(
+                r#"
+                discord_token = ""
+
+                [channels]
+                guild = 1
+                officer = 2
+                "#,
+            )
+            .expect("Test config is invalid"),
+        };
+
+        *current = Some(Arc::new(
+            Config::from_source(&source).expect("Test config is invalid"),
+        ));
+    }
 }
 
 pub struct Config {
@@ -21,57 +104,875 @@ pub struct Config {
     pub server_port: u16,
 
     pub channels: Channels,
+    pub routes: Routes,
+    pub rate_limit: RateLimit,
+    pub reconnect: Reconnect,
+    /// Where the bot should stay, returning with `return_commands` whenever it's seen anywhere else
+    pub home: Location,
+    /// Commands sent whenever the bot is seen somewhere other than `home`, such as `/lobby` if it should stay in a lobby
+    pub return_commands: Vec<String>,
+    /// Where the bridge keeps what it needs to remember between restarts, such as linked accounts
+    pub data_dir: PathBuf,
+    pub discord: Discord,
+    pub colours: Colours,
+    pub roles: Roles,
+    pub logger: Logger,
 }
 
 pub struct Channels {
     pub guild: u64,
     pub officer: u64,
+    /// Bridged to the bot's party chat, if set
+    pub party: Option<u64>,
+    /// Where whispers to the bot are sent, and where replying to one whispers back
+    pub whispers: Option<u64>,
+    /// Where moderation actions are logged, if set
+    pub audit: Option<u64>,
+}
+
+/// The Discord channels each kind of Minecraft event is sent to. An empty list means the event isn't sent anywhere
+pub struct Routes {
+    pub guild: Vec<u64>,
+    pub officer: Vec<u64>,
+    pub toggles: Vec<u64>,
+    pub guild_events: Vec<u64>,
+    pub member_moderation: Vec<u64>,
+    pub chat_moderation: Vec<u64>,
+    pub party: Vec<u64>,
+    pub whispers: Vec<u64>,
+    /// Whether whispers are also sent by DM to the Discord user linked to the player, who can reply from there
+    pub whisper_dms: bool,
+}
+
+/// A kind of Minecraft event which can be routed to its own Discord channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// Messages sent in a Minecraft chat
+    Chat(Chat),
+    /// Guild members connecting to or disconnecting from the server
+    Toggle,
+    /// Guild members joining, leaving, being kicked, promoted or demoted
+    GuildEvent,
+    /// A guild member being muted or unmuted
+    MemberModeration,
+    /// The whole guild chat being muted or unmuted
+    ChatModeration,
+    /// Private messages sent to the bot
+    Whisper,
+}
+
+impl Routes {
+    pub fn channels(&self, route: Route) -> &[u64] {
+        match route {
+            Route::Chat(Chat::Guild) => &self.guild,
+            Route::Chat(Chat::Officer) => &self.officer,
+            Route::Chat(Chat::Party) => &self.party,
+            Route::Toggle => &self.toggles,
+            Route::GuildEvent => &self.guild_events,
+            Route::MemberModeration => &self.member_moderation,
+            Route::ChatModeration => &self.chat_moderation,
+            Route::Whisper => &self.whispers,
+        }
+    }
+}
+
+pub struct RateLimit {
+    /// The number of commands which can be sent back to back
+    pub burst: u32,
+    /// The number of ticks it takes to be able to send another command
+    pub refill_ticks: u32,
+}
+
+pub struct Reconnect {
+    /// The delay before the first reconnection attempt, doubled for every attempt after it
+    pub base_delay: Duration,
+    /// The longest delay between two reconnection attempts
+    pub max_delay: Duration,
+    /// The number of attempts before giving up on reconnecting
+    pub max_attempts: u32,
+    /// How long a connection has to stay up before the attempts start over
+    pub stable_after: Duration,
+}
+
+pub struct Discord {
+    /// How long to wait for Minecraft to respond to a command
+    pub timeout: Duration,
+    /// The name given to the webhooks which send Minecraft messages
+    pub webhook_name: String,
+    /// The text shown after "Watching" in the bot's presence
+    pub presence: String,
+    /// Where player avatars are fetched from, with `{ign}` replaced by the player's name
+    pub avatar_url: String,
+    /// The emoji added to messages which reached Minecraft, or `None` to not react
+    pub success_reaction: Option<String>,
+    /// The emoji added to messages until they reach Minecraft, or `None` to not react
+    pub pending_reaction: Option<String>,
+    /// How long after being sent a message can be edited to send it again, zero to ignore edits
+    pub edit_window: Duration,
+}
+
+pub struct Roles {
+    /// Whether to add and remove roles, rather than only reporting what would change
+    pub sync: bool,
+    /// The Discord role given to members of each guild rank
+    pub ranks: BTreeMap<String, u64>,
+    /// The rank players are given when they join the guild
+    pub join_rank: String,
+}
+
+pub struct Logger {
+    /// Where log lines are posted, if set
+    pub channel: Option<u64>,
+    /// The least severe level which is posted
+    pub level: Level,
+    /// How often log lines are posted, with repeated lines combined in between
+    pub interval: Duration,
+}
+
+pub struct Colours {
+    pub green: u32,
+    pub yellow: u32,
+    pub red: u32,
 }
 
 impl Config {
-    pub fn new_from_env() -> Result<Config, EnvError> {
-        Ok(Config {
-            discord_token: var("DISCORD_TOKEN")?,
-            email: var("EMAIL").ok(),
-            server_address: var("SERVER_ADDRESS").unwrap_or_else(|_| {
-                if cfg!(debug_assertions) {
-                    "localhost".to_string()
-                } else {
-                    "mc.hypixel.io".to_string()
-                }
-            }),
-            server_port: match var("SERVER_PORT") {
-                Ok(port) => port.parse()?,
-                Err(_) => 25565,
+    /// Read the config from `bridge.toml` (or the file at `CONFIG_PATH`), letting environment variables override it
+    pub fn load() -> Result<Config, EnvError> {
+        let env = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value)))
+            .collect::<HashMap<_, _>>();
+        let path = env
+            .get("CONFIG_PATH")
+            .and_then(|path| path.to_str())
+            .unwrap_or(DEFAULT_PATH)
+            .to_string();
+
+        Config::from_source(&Source::read(path, env)?)
+    }
+
+    fn from_source(source: &Source) -> Result<Config, EnvError> {
+        let channels = Channels {
+            guild: source.required("GUILD_CHANNEL_ID", "channels.guild")?,
+            officer: source.required("OFFICER_CHANNEL_ID", "channels.officer")?,
+            party: source.get("PARTY_CHANNEL_ID", "channels.party")?,
+            whispers: source.get("WHISPER_CHANNEL_ID", "channels.whispers")?,
+            audit: source.get("AUDIT_CHANNEL_ID", "channels.audit")?,
+        };
+        let route = |name: &str, key: &str, default: &[u64]| {
+            source
+                .get::<ChannelList>(name, key)
+                .map(|list| list.map_or_else(|| default.to_vec(), |list| list.0))
+        };
+
+        let config = Config {
+            discord_token: source.required("DISCORD_TOKEN", "discord_token")?,
+            email: source.get("EMAIL", "email")?,
+            server_address: source
+                .get("SERVER_ADDRESS", "server_address")?
+                .unwrap_or_else(|| {
+                    if cfg!(debug_assertions) {
+                        "localhost".to_string()
+                    } else {
+                        "mc.hypixel.io".to_string()
+                    }
+                }),
+            server_port: source.get("SERVER_PORT", "server_port")?.unwrap_or(25565),
+            routes: Routes {
+                guild: route("ROUTE_GUILD", "routes.guild", &[channels.guild])?,
+                officer: route("ROUTE_OFFICER", "routes.officer", &[channels.officer])?,
+                toggles: route("ROUTE_TOGGLES", "routes.toggles", &[channels.guild])?,
+                guild_events: route(
+                    "ROUTE_GUILD_EVENTS",
+                    "routes.guild_events",
+                    &[channels.guild, channels.officer],
+                )?,
+                member_moderation: route(
+                    "ROUTE_MEMBER_MODERATION",
+                    "routes.member_moderation",
+                    &[channels.officer],
+                )?,
+                chat_moderation: route(
+                    "ROUTE_CHAT_MODERATION",
+                    "routes.chat_moderation",
+                    &[channels.guild, channels.officer],
+                )?,
+                party: route("ROUTE_PARTY", "routes.party", channels.party.as_slice())?,
+                whispers: route(
+                    "ROUTE_WHISPERS",
+                    "routes.whispers",
+                    channels.whispers.as_slice(),
+                )?,
+                whisper_dms: source
+                    .get("ROUTE_WHISPER_DMS", "routes.whisper_dms")?
+                    .unwrap_or(false),
+            },
+            channels,
+            rate_limit: RateLimit {
+                burst: source
+                    .get("RATE_LIMIT_BURST", "rate_limit.burst")?
+                    .unwrap_or(1),
+                refill_ticks: source
+                    .get("RATE_LIMIT_REFILL_TICKS", "rate_limit.refill_ticks")?
+                    .unwrap_or(5),
             },
-            channels: Channels {
-                guild: var("GUILD_CHANNEL_ID")?.parse()?,
-                officer: var("OFFICER_CHANNEL_ID")?.parse()?,
+            reconnect: Reconnect {
+                base_delay: Duration::from_secs(
+                    source
+                        .get("RECONNECT_BASE_DELAY", "reconnect.base_delay")?
+                        .unwrap_or(5),
+                ),
+                max_delay: Duration::from_secs(
+                    source
+                        .get("RECONNECT_MAX_DELAY", "reconnect.max_delay")?
+                        .unwrap_or(5 * 60),
+                ),
+                max_attempts: source
+                    .get("RECONNECT_MAX_ATTEMPTS", "reconnect.max_attempts")?
+                    .unwrap_or(10),
+                stable_after: Duration::from_secs(
+                    source
+                        .get("RECONNECT_STABLE_AFTER", "reconnect.stable_after")?
+                        .unwrap_or(60),
+                ),
             },
+            home: source
+                .get::<Home>("HOME_LOCATION", "home_location")?
+                .map_or(Location::Lobby, |home| home.0),
+            return_commands: source
+                .get::<Commands>("RETURN_COMMANDS", "return_commands")?
+                .map(|commands| commands.0)
+                .unwrap_or_default(),
+            data_dir: source
+                .get("DATA_DIR", "data_dir")?
+                .unwrap_or_else(|| PathBuf::from("data")),
+            discord: Discord {
+                timeout: Duration::from_secs(
+                    source
+                        .get("COMMAND_TIMEOUT", "discord.timeout")?
+                        .unwrap_or(10),
+                ),
+                webhook_name: source
+                    .get("WEBHOOK_NAME", "discord.webhook_name")?
+                    .unwrap_or_else(|| "Bridge".to_string()),
+                presence: source
+                    .get("PRESENCE", "discord.presence")?
+                    .unwrap_or_else(|| "Guild Chat".to_string()),
+                avatar_url: source
+                    .get("AVATAR_URL", "discord.avatar_url")?
+                    .unwrap_or_else(|| "https://mc-heads.net/avatar/{ign}/512".to_string()),
+                // An empty emoji turns the reaction off
+                success_reaction: source
+                    .get::<String>("SUCCESS_REACTION", "discord.success_reaction")?
+                    .map_or_else(|| Some("✅".to_string()), non_empty),
+                pending_reaction: source
+                    .get::<String>("PENDING_REACTION", "discord.pending_reaction")?
+                    .and_then(non_empty),
+                edit_window: Duration::from_secs(
+                    source
+                        .get("EDIT_WINDOW", "discord.edit_window")?
+                        .unwrap_or(60),
+                ),
+            },
+            colours: Colours {
+                green: source
+                    .get::<Colour>("COLOUR_GREEN", "colours.green")?
+                    .map_or(0x47f04a, |colour| colour.0),
+                yellow: source
+                    .get::<Colour>("COLOUR_YELLOW", "colours.yellow")?
+                    .map_or(0xff8c00, |colour| colour.0),
+                red: source
+                    .get::<Colour>("COLOUR_RED", "colours.red")?
+                    .map_or(0xf04a47, |colour| colour.0),
+            },
+            roles: Roles {
+                sync: source.get("ROLE_SYNC", "roles.sync")?.unwrap_or(false),
+                ranks: source
+                    .get::<RankRoles>("ROLE_RANKS", "roles.ranks")?
+                    .map(|ranks| ranks.0)
+                    .unwrap_or_default(),
+                join_rank: source
+                    .get("ROLE_JOIN_RANK", "roles.join_rank")?
+                    .unwrap_or_else(|| "Member".to_string()),
+            },
+            logger: Logger {
+                channel: source.get("LOG_CHANNEL_ID", "logger.channel")?,
+                level: source
+                    .get::<LogLevel>("LOG_LEVEL", "logger.level")?
+                    .map_or(Level::WARN, |level| level.0),
+                interval: Duration::from_secs(
+                    source.get("LOG_INTERVAL", "logger.interval")?.unwrap_or(10),
+                ),
+            },
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Catch values which would only cause an error once they're used
+    fn validate(&self) -> Result<(), EnvError> {
+        let invalid =
+            |name: &str, reason: &str| Err(EnvError::Invalid(format!("{name}: {reason}")));
+
+        if self.channels.guild == 0 {
+            return invalid("GUILD_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.channels.officer == 0 {
+            return invalid("OFFICER_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.channels.party == Some(0) {
+            return invalid("PARTY_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.channels.whispers == Some(0) {
+            return invalid("WHISPER_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.channels.audit == Some(0) {
+            return invalid("AUDIT_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.logger.channel == Some(0) {
+            return invalid("LOG_CHANNEL_ID", "channel IDs can't be 0");
+        }
+        if self.logger.interval.is_zero() {
+            return invalid("LOG_INTERVAL", "must be at least 1 second");
+        }
+        for (name, channels) in [
+            ("ROUTE_GUILD", &self.routes.guild),
+            ("ROUTE_OFFICER", &self.routes.officer),
+            ("ROUTE_TOGGLES", &self.routes.toggles),
+            ("ROUTE_GUILD_EVENTS", &self.routes.guild_events),
+            ("ROUTE_MEMBER_MODERATION", &self.routes.member_moderation),
+            ("ROUTE_CHAT_MODERATION", &self.routes.chat_moderation),
+            ("ROUTE_PARTY", &self.routes.party),
+            ("ROUTE_WHISPERS", &self.routes.whispers),
+        ] {
+            if channels.contains(&0) {
+                return invalid(name, "IDs can't be 0");
+            }
+        }
+        if self.roles.ranks.values().any(|role| *role == 0) {
+            return invalid("ROLE_RANKS", "role IDs can't be 0");
+        }
+        if self.rate_limit.burst == 0 {
+            return invalid("RATE_LIMIT_BURST", "must be at least 1");
+        }
+        if self.rate_limit.refill_ticks == 0 {
+            return invalid("RATE_LIMIT_REFILL_TICKS", "must be at least 1");
+        }
+        if self.reconnect.base_delay > self.reconnect.max_delay {
+            return invalid(
+                "RECONNECT_BASE_DELAY",
+                "can't be longer than RECONNECT_MAX_DELAY",
+            );
+        }
+        if self.discord.timeout.is_zero() {
+            return invalid("COMMAND_TIMEOUT", "must be at least 1 second");
+        }
+        if !(1..=80).contains(&self.discord.webhook_name.chars().count()) {
+            return invalid("WEBHOOK_NAME", "must be between 1 and 80 characters");
+        }
+        if ["discord", "clyde"]
+            .iter()
+            .any(|banned| self.discord.webhook_name.to_lowercase().contains(banned))
+        {
+            return invalid("WEBHOOK_NAME", "can't contain \"discord\" or \"clyde\"");
+        }
+        if !(1..=128).contains(&self.discord.presence.chars().count()) {
+            return invalid("PRESENCE", "must be between 1 and 128 characters");
+        }
+        if !self.discord.avatar_url.starts_with("https://")
+            && !self.discord.avatar_url.starts_with("http://")
+        {
+            return invalid("AVATAR_URL", "must be a http(s) URL");
+        }
+        if !self.discord.avatar_url.contains("{ign}") {
+            return invalid("AVATAR_URL", "must contain {ign}");
+        }
+        for (name, reaction) in [
+            ("SUCCESS_REACTION", &self.discord.success_reaction),
+            ("PENDING_REACTION", &self.discord.pending_reaction),
+        ] {
+            // Reactions are added as Unicode emojis, so a custom emoji would fail on every message
+            if reaction
+                .as_deref()
+                .is_some_and(|emoji| emojis::get(emoji).is_none())
+            {
+                return invalid(
+                    name,
+                    "must be a Unicode emoji, custom emojis aren't supported",
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// The config file, with environment variables taking precedence over it
+struct Source {
+    path: String,
+    /// The environment variables, passed in so tests don't depend on the environment they run in
+    env: HashMap<String, OsString>,
+    file: toml::Table,
+}
+
+impl Source {
+    /// Read the config file at `path`, which is allowed to not exist
+    fn read(path: String, env: HashMap<String, OsString>) -> Result<Self, EnvError> {
+        let file = match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|error| EnvError::File(format!("{path}: {error}")))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(error) => return Err(EnvError::File(format!("{path}: {error}"))),
+        };
+
+        Ok(Self { path, env, file })
+    }
+
+    /// Get a value from the environment variable `name`, or from the dotted `key` in the config file
+    fn get<T>(&self, name: &str, key: &str) -> Result<Option<T>, EnvError>
+    where
+        T: FromStr + DeserializeOwned,
+        T::Err: Display,
+    {
+        if let Some(value) = self.env.get(name) {
+            let Some(value) = value.to_str() else {
+                return Err(EnvError::Invalid(format!("{name}: not valid unicode")));
+            };
+
+            return value
+                .parse()
+                .map(Some)
+                .map_err(|error| EnvError::Invalid(format!("{name}: {error}")));
+        }
+
+        let mut parts = key.split('.').peekable();
+        let mut table = &self.file;
+
+        while let Some(part) = parts.next() {
+            let Some(value) = table.get(part) else {
+                return Ok(None);
+            };
+
+            if parts.peek().is_none() {
+                return value.clone().try_into().map(Some).map_err(|error| {
+                    EnvError::Invalid(format!(
+                        "{key} in {path}: {message}",
+                        path = self.path,
+                        message = error.message()
+                    ))
+                });
+            }
+
+            let Some(inner) = value.as_table() else {
+                return Err(EnvError::Invalid(format!(
+                    "{part} in {path}: expected a table",
+                    path = self.path
+                )));
+            };
+            table = inner;
+        }
+
+        Ok(None)
+    }
+
+    /// Like [`Source::get`], but the value has to be set somewhere
+    fn required<T>(&self, name: &str, key: &str) -> Result<T, EnvError>
+    where
+        T: FromStr + DeserializeOwned,
+        T::Err: Display,
+    {
+        self.get(name, key)?.ok_or_else(|| {
+            EnvError::Missing(format!("{name} (or {key} in {path})", path = self.path))
         })
     }
 }
 
+/// A list of channel IDs, separated by `,` in environment variables
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct ChannelList(Vec<u64>);
+
+impl FromStr for ChannelList {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Guild ranks and the roles given to them, written as `Member=1,Officer=2` in environment variables
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct RankRoles(BTreeMap<String, u64>);
+
+impl FromStr for RankRoles {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .filter(|pair| !pair.trim().is_empty())
+            .map(|pair| {
+                let (rank, role) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected rank=role, found {pair}"))?;
+                let role = role.trim().parse().map_err(|error| format!("{error}"))?;
+
+                Ok((rank.trim().to_string(), role))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// A list of commands, separated by `;` in environment variables
+#[derive(Deserialize)]
+#[serde(from = "Vec<String>")]
+struct Commands(Vec<String>);
+
+impl From<Vec<String>> for Commands {
+    fn from(commands: Vec<String>) -> Self {
+        Self(
+            commands
+                .iter()
+                .map(|command| command.trim())
+                .filter(|command| !command.is_empty())
+                .map(|command| format!("/{}", command.trim_start_matches('/')))
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Commands {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(value
+            .split(';')
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+            .into())
+    }
+}
+
+/// Where the bot should stay, either `lobby` or `limbo`
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct Home(Location);
+
+impl TryFrom<String> for Home {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for Home {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "lobby" => Ok(Self(Location::Lobby)),
+            "limbo" => Ok(Self(Location::Limbo)),
+            _ => Err(format!("{value} is not lobby or limbo")),
+        }
+    }
+}
+
+/// An RGB colour, written as `#47f04a` in environment variables
+#[derive(Deserialize)]
+#[serde(try_from = "u32")]
+struct Colour(u32);
+
+impl TryFrom<u32> for Colour {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0..=0xffffff => Ok(Self(value)),
+            _ => Err(format!("{value:#x} is not an RGB colour")),
+        }
+    }
+}
+
+impl FromStr for Colour {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value.trim_start_matches('#').trim_start_matches("0x");
+
+        u32::from_str_radix(hex, 16)
+            .map_err(|error| error.to_string())
+            .and_then(Colour::try_from)
+    }
+}
+
+/// A log level, such as `warn` or `error`
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct LogLevel(Level);
+
+impl TryFrom<String> for LogLevel {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .trim()
+            .parse()
+            .map(Self)
+            .map_err(|_| format!("{value} is not a log level"))
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum EnvError {
-    #[error("Missing environment variable: {0:?}")]
+    #[error("Missing config value: {0}")]
     Missing(String),
 
-    #[error("Invalid environment variable: {0:?}")]
+    #[error("Invalid config value: {0}")]
     Invalid(String),
+
+    #[error("Could not read the config file: {0}")]
+    File(String),
 }
 
-impl From<std::env::VarError> for EnvError {
-    fn from(error: std::env::VarError) -> Self {
-        match error {
-            std::env::VarError::NotPresent => EnvError::Missing(error.to_string()),
-            std::env::VarError::NotUnicode(_) => EnvError::Invalid(error.to_string()),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn source(file: &str) -> Source {
+        Source {
+            path: DEFAULT_PATH.to_string(),
+            env: HashMap::new(),
+            file: toml::from_str(file).unwrap(),
         }
     }
-}
 
-impl From<std::num::ParseIntError> for EnvError {
-    fn from(error: std::num::ParseIntError) -> Self {
-        EnvError::Invalid(error.to_string())
+    #[test]
+    fn file() {
+        let source = source("server_port = 25566\n[channels]\nguild = 1\nofficer = 2");
+
+        assert_eq!(
+            source.get::<u16>("SERVER_PORT", "server_port").unwrap(),
+            Some(25566)
+        );
+        assert_eq!(
+            source
+                .get::<u64>("GUILD_CHANNEL_ID", "channels.guild")
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            source.get::<u64>("MISSING", "channels.missing").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let mut source = source("server_port = 25566");
+        source.env.insert("SERVER_PORT".to_string(), "25567".into());
+
+        assert_eq!(
+            source.get::<u16>("SERVER_PORT", "server_port").unwrap(),
+            Some(25567)
+        );
+    }
+
+    #[test_case("server_port = \"abc\"", "Invalid config value: server_port in bridge.toml: invalid type: string \"abc\", expected u16" ; "Wrong type")]
+    #[test_case("server_port = 1", "Missing config value: DISCORD_TOKEN (or discord_token in bridge.toml)" ; "Missing")]
+    fn errors(file: &str, message: &str) {
+        let source = source(file);
+        let error = source
+            .get::<u16>("SERVER_PORT", "server_port")
+            .and_then(|_| source.required::<String>("DISCORD_TOKEN", "discord_token"))
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), message);
+    }
+
+    #[test_case("#47f04a", Some(0x47f04a) ; "Hash")]
+    #[test_case("0xff8c00", Some(0xff8c00) ; "Prefixed")]
+    #[test_case("1000000", None ; "Too large")]
+    #[test_case("green", None ; "Not hex")]
+    fn colour(input: &str, expected: Option<u32>) {
+        assert_eq!(
+            input.parse::<Colour>().ok().map(|colour| colour.0),
+            expected
+        );
+    }
+
+    #[test_case("", &[] ; "Suppressed")]
+    #[test_case("1", &[1] ; "Single")]
+    #[test_case("1, 2,", &[1, 2] ; "Mirrored")]
+    fn channel_list(input: &str, expected: &[u64]) {
+        assert_eq!(input.parse::<ChannelList>().unwrap().0, expected);
+    }
+
+    #[test]
+    fn default_routes() {
+        let config = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[routes]\ntoggles = []\nguild = [1, 3]",
+        ))
+        .unwrap();
+
+        assert_eq!(config.routes.channels(Route::Chat(Chat::Guild)), [1, 3]);
+        assert_eq!(config.routes.channels(Route::Chat(Chat::Officer)), [2]);
+        assert!(config.routes.channels(Route::Toggle).is_empty());
+        assert_eq!(config.routes.channels(Route::GuildEvent), [1, 2]);
+        assert!(config.routes.channels(Route::Chat(Chat::Party)).is_empty());
+    }
+
+    #[test]
+    fn party_and_whispers() {
+        let config = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\nparty = 3\nwhispers = 4\n[routes]\nwhisper_dms = true",
+        ))
+        .unwrap();
+
+        assert_eq!(config.routes.channels(Route::Chat(Chat::Party)), [3]);
+        assert_eq!(config.routes.channels(Route::Whisper), [4]);
+        assert!(config.routes.whisper_dms);
+        assert_eq!(config.channels.audit, None);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let config = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2",
+        ))
+        .unwrap();
+
+        // Commands are spaced out from the start, as sending several at once gets them rejected
+        assert_eq!(config.rate_limit.burst, 1);
+        assert_eq!(config.rate_limit.refill_ticks, 5);
+    }
+
+    #[test]
+    fn audit() {
+        let config = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\naudit = 3",
+        ))
+        .unwrap();
+
+        assert_eq!(config.channels.audit, Some(3));
+    }
+
+    #[test_case("Member=1, Guild Master = 2", &[("Guild Master", 2), ("Member", 1)] ; "Valid")]
+    #[test_case("", &[] ; "Empty")]
+    fn rank_roles(input: &str, expected: &[(&str, u64)]) {
+        let RankRoles(ranks) = input.parse().unwrap();
+
+        assert_eq!(
+            ranks
+                .iter()
+                .map(|(rank, role)| (rank.as_str(), *role))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test_case("Member" ; "No role")]
+    #[test_case("Member=abc" ; "Not an ID")]
+    fn invalid_rank_roles(input: &str) {
+        assert!(input.parse::<RankRoles>().is_err());
+    }
+
+    #[test_case("warn", Level::WARN ; "Lowercase")]
+    #[test_case(" ERROR", Level::ERROR ; "Uppercase")]
+    fn log_level(input: &str, expected: Level) {
+        assert_eq!(input.parse::<LogLevel>().unwrap().0, expected);
+    }
+
+    #[test_case("lobby", Location::Lobby ; "Lobby")]
+    #[test_case(" Limbo", Location::Limbo ; "Limbo")]
+    fn home(input: &str, expected: Location) {
+        assert_eq!(input.parse::<Home>().unwrap().0, expected);
+    }
+
+    #[test]
+    fn invalid_home() {
+        assert!("skyblock".parse::<Home>().is_err());
+    }
+
+    #[test]
+    fn logger() {
+        let config = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[logger]\nchannel = 3\nlevel = \"error\"",
+        ))
+        .unwrap();
+
+        assert_eq!(config.logger.channel, Some(3));
+        assert_eq!(config.logger.level, Level::ERROR);
+        assert!(Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[logger]\nlevel = \"loud\"",
+        ))
+        .is_err());
+    }
+
+    #[test_case("", Some("✅"), None ; "Default")]
+    #[test_case("success_reaction = \"👍\"\npending_reaction = \"⏳\"", Some("👍"), Some("⏳") ; "Set")]
+    #[test_case("success_reaction = \"\"", None, None ; "Turned off")]
+    fn reactions(discord: &str, success: Option<&str>, pending: Option<&str>) {
+        let config = Config::from_source(&source(&format!(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[discord]\n{discord}"
+        )))
+        .unwrap();
+
+        assert_eq!(config.discord.success_reaction.as_deref(), success);
+        assert_eq!(config.discord.pending_reaction.as_deref(), pending);
+    }
+
+    #[test_case("success_reaction = \"<:check:123456789>\"" ; "Custom emoji")]
+    #[test_case("pending_reaction = \"wait\"" ; "Not an emoji")]
+    fn invalid_reactions(discord: &str) {
+        assert!(Config::from_source(&source(&format!(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[discord]\n{discord}"
+        )))
+        .is_err());
+    }
+
+    #[test]
+    fn commands() {
+        let Commands(commands) = "lobby; /g online;".parse().unwrap();
+
+        assert_eq!(commands, ["/lobby", "/g online"]);
+    }
+
+    #[test]
+    fn validation() {
+        let error = Config::from_source(&source(
+            "discord_token = \"\"\n[channels]\nguild = 1\nofficer = 2\n[discord]\navatar_url = \"https://example.com\"",
+        ))
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid config value: AVATAR_URL: must contain {ign}"
+        );
     }
 }
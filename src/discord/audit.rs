@@ -0,0 +1,102 @@
+use super::colours;
+use crate::{
+    config, minecraft,
+    payloads::{
+        command::{MinecraftCommand, Priority},
+        events::{ChatEvent, GuildEvent},
+    },
+};
+use twilight_http::Client as HttpClient;
+use twilight_model::{
+    channel::message::Embed,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+/// Whether a command sent from Discord is written to the audit channel
+pub fn is_audited(command: &MinecraftCommand) -> bool {
+    command.priority() == Priority::Moderation || matches!(command, MinecraftCommand::Execute(_))
+}
+
+/// An entry for a command sent from Discord, with the response shown to whoever used it
+pub fn command_entry(user: Id<UserMarker>, command: &str, response: &Embed) -> Embed {
+    EmbedBuilder::new()
+        .title("Moderation")
+        .field(EmbedFieldBuilder::new("Invoked By", format!("<@{user}>")).inline())
+        .field(EmbedFieldBuilder::new("Command", format!("`{command}`")).inline())
+        .field(EmbedFieldBuilder::new(
+            "Outcome",
+            response
+                .description
+                .clone()
+                .unwrap_or_else(|| "No response".to_string()),
+        ))
+        .footer(EmbedFooterBuilder::new("From Discord"))
+        .color(response.color.unwrap_or_else(colours::yellow))
+        .build()
+}
+
+/// An entry for a moderation action seen in game, unless the bot took it, as those are logged when the command is used
+pub fn event_entry(event: &ChatEvent) -> Option<Embed> {
+    let username = minecraft::USERNAME.get()?.read().clone();
+    let (by, outcome) = in_game(event, &username)?;
+
+    Some(
+        EmbedBuilder::new()
+            .title("Moderation")
+            .field(EmbedFieldBuilder::new("Invoked By", format!("`{by}`")).inline())
+            .field(EmbedFieldBuilder::new("Outcome", outcome))
+            .footer(EmbedFooterBuilder::new("In Game"))
+            .color(colours::yellow())
+            .build(),
+    )
+}
+
+/// Who took a moderation action seen in game, and what happened
+fn in_game<'a>(event: &ChatEvent<'a>, bot: &str) -> Option<(&'a str, String)> {
+    use crate::payloads::events::Moderation;
+
+    let (by, outcome) = match event {
+        ChatEvent::Moderation(
+            moderation @ (Moderation::Mute { by, .. } | Moderation::Unmute { by, .. }),
+        ) => (*by, moderation.to_string()),
+        ChatEvent::GuildEvent(kick @ GuildEvent::Kick { by, .. }) => (*by, kick.to_string()),
+        _ => return None,
+    };
+
+    (!by.eq_ignore_ascii_case(bot)).then_some((by, outcome))
+}
+
+/// Send an entry to the audit channel, if there is one
+pub async fn log(http: &HttpClient, entry: Embed) {
+    let Some(channel) = config().channels.audit else {
+        return;
+    };
+
+    if let Err(err) = http
+        .create_message(Id::new(channel))
+        .embeds(&[entry])
+        .expect("Invalid audit log entry")
+        .await
+    {
+        tracing::error!("Failed to send audit log entry: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("neytwoa has unmuted neyoa", Some(("neytwoa", "neytwoa unmuted neyoa")) ; "Unmute")]
+    #[test_case("neytwoa has unmuted the guild chat!", Some(("neytwoa", "neytwoa unmuted Guild Chat")) ; "Unmute chat")]
+    #[test_case("neyoa was kicked from the guild by neytwoa!", Some(("neytwoa", "neytwoa kicked neyoa from the guild")) ; "Kick")]
+    #[test_case("Bridge has muted neyoa for 30d", None ; "By the bot")]
+    #[test_case("neyoa left the guild!", None ; "Not moderation")]
+    fn seen_in_game(input: &str, expected: Option<(&str, &str)>) {
+        assert_eq!(
+            in_game(&ChatEvent::from(input), "bridge"),
+            expected.map(|(by, outcome)| (by, outcome.to_string()))
+        );
+    }
+}
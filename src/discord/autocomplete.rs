@@ -1,11 +1,18 @@
-use crate::minecraft;
+use crate::{minecraft, storage};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use sorted_vec::SortedSet;
 use std::{collections::HashSet, sync::Arc};
 
-static USERNAMES: Lazy<Mutex<HashSet<Arc<str>>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static USERNAMES: Lazy<Mutex<HashSet<Arc<str>>>> = Lazy::new(|| {
+    Mutex::new(storage::read(|data| {
+        data.usernames
+            .iter()
+            .map(|username| Arc::from(username.as_str()))
+            .collect()
+    }))
+});
 static MATCHER: Lazy<SkimMatcherV2> = Lazy::new(|| SkimMatcherV2::default().ignore_case());
 
 #[derive(Eq)]
@@ -34,11 +41,33 @@ pub fn add_username(username: &str) {
         return; // Don't add the bot's username to autocomplete
     }
 
-    USERNAMES.lock().insert(Arc::from(username));
+    if USERNAMES.lock().insert(Arc::from(username)) {
+        storage::write(|data| data.usernames.insert(username.to_string()));
+    }
 }
 
 pub fn remove_username(username: &str) {
-    USERNAMES.lock().remove(username);
+    if USERNAMES.lock().remove(username) {
+        storage::write(|data| data.usernames.remove(username));
+    }
+}
+
+/// Replace every username with a complete list of guild members
+pub fn replace_usernames<'a>(usernames: impl IntoIterator<Item = &'a str>) {
+    let bot = minecraft::USERNAME.wait().read().clone();
+
+    let usernames = usernames
+        .into_iter()
+        .filter(|username| *username != bot)
+        .collect::<Vec<_>>();
+
+    storage::write(|data| {
+        data.usernames = usernames
+            .iter()
+            .map(|username| username.to_string())
+            .collect()
+    });
+    *USERNAMES.lock() = usernames.into_iter().map(Arc::from).collect();
 }
 
 /// Returns a list of members that match the input, returning **all** matches.
@@ -1,36 +1,69 @@
+mod bridge;
 mod execute;
 mod guild;
 mod help;
+mod history;
+mod link;
+mod unlink;
 
-pub use {execute::ExecuteCommand, guild::GuildCommand, help::HelpCommand};
+pub use {
+    bridge::BridgeCommand, execute::ExecuteCommand, guild::GuildCommand, help::HelpCommand,
+    history::HistoryCommand, link::LinkCommand, unlink::UnlinkCommand,
+};
 
-use super::colours;
-use crate::payloads::{
-    command::{CommandPayload, MinecraftCommand},
-    events::RawChatEvent,
+use super::{colours, feedback::Collect};
+use crate::{
+    config,
+    payloads::{command::MinecraftCommand, events::RawChatEvent},
 };
 use macros::commands;
-use std::time::Duration;
+use once_cell::sync::OnceCell;
 use strum::EnumIs;
-use tokio::sync::{mpsc, oneshot};
 use twilight_interactions::command::{CommandOption, CreateCommand, CreateOption};
 use twilight_model::{
     application::{command::Command, interaction::application_command::CommandData},
     channel::message::Embed,
+    id::{marker::UserMarker, Id},
 };
 use twilight_util::builder::embed::EmbedBuilder;
 
 // Add new commands here!
-commands!(GuildCommand, HelpCommand, ExecuteCommand);
+commands!(
+    GuildCommand,
+    HelpCommand,
+    ExecuteCommand,
+    BridgeCommand,
+    LinkCommand,
+    UnlinkCommand,
+    HistoryCommand
+);
+
+/// The owner of the bot's application, or the owner of its team
+static OWNER: OnceCell<Id<UserMarker>> = OnceCell::new();
+
+/// Whether `user` owns the bot, and so can use commands which are [`RunCommand::owner_only`]
+pub fn is_owner(user: Option<Id<UserMarker>>) -> bool {
+    user.is_some_and(|user| OWNER.get() == Some(&user))
+}
 
 pub async fn register_commands(http: &twilight_http::Client) -> crate::Result<()> {
     let application_id = {
         let response = http.current_user_application().await?;
-        response
+        let application = response
             .model()
             .await
-            .expect("Could not deserialise response body")
-            .id
+            .expect("Could not deserialise response body");
+
+        match (&application.team, &application.owner) {
+            (Some(team), _) => OWNER.set(team.owner_user_id).ok(),
+            (None, Some(owner)) => OWNER.set(owner.id).ok(),
+            (None, None) => {
+                tracing::warn!("Could not find the bot's owner, owner only commands are disabled");
+                None
+            }
+        };
+
+        application.id
     };
 
     Ok(http
@@ -45,19 +78,30 @@ pub enum SlashCommandResponse {
     Success(String),
     Failure(String),
     Embed(Box<Embed>),
+    /// Several embeds, shown one at a time with buttons to move between them
+    Pages(Vec<Embed>),
     Timeout,
 }
 
 impl From<SlashCommandResponse> for Embed {
     fn from(value: SlashCommandResponse) -> Self {
         let (description, colour) = match value {
-            SlashCommandResponse::Success(description) => (description, colours::GREEN),
-            SlashCommandResponse::Failure(description) => (description.to_string(), colours::RED),
+            SlashCommandResponse::Success(description) => (description, colours::green()),
+            SlashCommandResponse::Failure(description) => (description.to_string(), colours::red()),
             SlashCommandResponse::Timeout => (
-                format!("Couldn't find any command response after {TIMEOUT_DELAY:?}"),
-                colours::RED,
+                format!(
+                    "Couldn't find any command response after {timeout:?}",
+                    timeout = config().discord.timeout
+                ),
+                colours::red(),
             ),
             SlashCommandResponse::Embed(embed) => return *embed,
+            SlashCommandResponse::Pages(pages) => {
+                return pages
+                    .into_iter()
+                    .next()
+                    .expect("Paginated response had no pages")
+            }
         };
 
         EmbedBuilder::new()
@@ -71,17 +115,37 @@ pub trait RunCommand: Send + Sync + 'static {
     /// The type of response that the command returns
     type Response: Send + Sync + 'static;
 
-    /// Get the command that will be sent to Minecraft, or the response if the command is invalid
-    fn get_command(&self) -> Result<MinecraftCommand, Self::Response>;
+    /// Get the command that will be sent to Minecraft for the `user` who used it, or the response if the command is invalid
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, Self::Response>;
 
     /// Check if the event is a response to the command, and return the response if it is
-    // TODO: Allow some events to consume multiple responses (needed for /g online, /g list, etc. and also /execute for better feedback)
     fn check_event(&self, event: RawChatEvent) -> Option<Self::Response> {
         unreachable!("Command should never call `check_event` ({event:?})")
     }
+
+    /// Whether only the owner of the bot can use the command
+    fn owner_only(&self) -> bool {
+        false
+    }
+
+    /// Whether the response spans several events, in which case [`RunCommand::collect_event`] and
+    /// [`RunCommand::finish`] are used instead of [`RunCommand::check_event`]
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// Check if the event is part of the response to the command
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        unreachable!("Command should never call `collect_event` ({event:?})")
+    }
+
+    /// Build the response from every event collected
+    fn finish(&self, events: Vec<RawChatEvent>) -> Self::Response {
+        unreachable!("Command should never call `finish` ({events:?})")
+    }
 }
 
-#[derive(CommandOption, CreateOption, Debug, Clone, Copy)]
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy, PartialEq)]
 pub enum TimeUnit {
     #[option(name = "Minutes", value = "m")]
     Minute,
@@ -101,46 +165,6 @@ impl From<TimeUnit> for char {
     }
 }
 
-const TIMEOUT_DELAY: Duration = Duration::from_secs(10);
-
-pub struct Feedback {
-    pub tx: mpsc::UnboundedSender<CommandPayload>,
-    pub rx: async_broadcast::InactiveReceiver<RawChatEvent>,
-}
-
-impl Feedback {
-    pub async fn execute<F, R>(&mut self, command: MinecraftCommand, f: F) -> Option<R>
-    where
-        F: Fn(RawChatEvent) -> Option<R>,
-    {
-        let (verify_tx, verify_rx) = oneshot::channel();
-
-        self.tx
-            .send(CommandPayload::new(command, verify_tx))
-            .expect("Minecraft payload receiver was dropped");
-
-        verify_rx
-            .await
-            .expect("Minecraft command sent verifier was dropped");
-
-        tokio::select! {
-            biased;
-            result = async {
-                while let Ok(payload) = self.rx.activate_cloned().recv().await {
-                    if let Some(result) = f(payload) {
-                        return result;
-                    }
-                }
-
-                unreachable!("The feedback channel was closed")
-            } => Some(result),
-            _ = async {
-                tokio::time::sleep(TIMEOUT_DELAY).await;
-            } => None,
-        }
-    }
-}
-
 mod macros {
     /// Generate the `get_commands` and `get_run_command` functions for the given commands
     macro_rules! commands {
@@ -172,10 +196,18 @@ pub mod testing {
     use crate::minecraft::USERNAME;
     use parking_lot::RwLock;
 
+    /// The Discord user commands are used by in tests
+    const TEST_USER: Id<UserMarker> = Id::new(1);
+
     pub fn test_command<R>(command: impl RunCommand<Response = R>, message: &'static str) -> R {
+        if command.is_streaming() {
+            return test_streaming_command(command, &[message]);
+        }
+
+        crate::storage::init_for_tests();
         USERNAME.set(RwLock::new("neytwoa".to_string())).ok();
 
-        if let Err(response) = command.get_command() {
+        if let Err(response) = command.get_command(TEST_USER) {
             return response;
         }
 
@@ -183,4 +215,35 @@ pub mod testing {
             .check_event(RawChatEvent(message.to_string()))
             .expect("No response was returned")
     }
+
+    pub fn test_streaming_command<R>(
+        command: impl RunCommand<Response = R>,
+        messages: &[&'static str],
+    ) -> R {
+        crate::storage::init_for_tests();
+        USERNAME.set(RwLock::new("neytwoa".to_string())).ok();
+
+        if let Err(response) = command.get_command(TEST_USER) {
+            return response;
+        }
+
+        let mut events = vec![];
+
+        for message in messages {
+            let event = RawChatEvent(message.to_string());
+
+            match command.collect_event(&event) {
+                Collect::Ignore => continue,
+                Collect::Line => events.push(event),
+                Collect::Last => {
+                    events.push(event);
+                    break;
+                }
+            }
+        }
+
+        assert!(!events.is_empty(), "No response was returned");
+
+        command.finish(events)
+    }
 }
@@ -0,0 +1,65 @@
+mod reload;
+mod roles;
+
+use super::{RunCommand, SlashCommandResponse};
+use crate::{
+    discord::feedback::Collect,
+    payloads::{command::MinecraftCommand, events::RawChatEvent},
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "bridge",
+    desc = "Bridge management commands",
+    default_permissions = "permissions",
+    dm_permission = true
+)]
+pub enum BridgeCommand {
+    #[command(name = "reload")]
+    Reload(reload::ReloadCommand),
+
+    #[command(name = "roles")]
+    Roles(roles::RolesCommand),
+}
+
+fn permissions() -> Permissions {
+    Permissions::ADMINISTRATOR
+}
+
+impl BridgeCommand {
+    fn as_run_command(&self) -> &dyn RunCommand<Response = SlashCommandResponse> {
+        match self {
+            Self::Reload(command) => command,
+            Self::Roles(command) => command,
+        }
+    }
+}
+
+impl RunCommand for BridgeCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, Self::Response> {
+        self.as_run_command().get_command(user)
+    }
+
+    fn owner_only(&self) -> bool {
+        self.as_run_command().owner_only()
+    }
+
+    fn is_streaming(&self) -> bool {
+        self.as_run_command().is_streaming()
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        self.as_run_command().collect_event(event)
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> Self::Response {
+        self.as_run_command().finish(events)
+    }
+}
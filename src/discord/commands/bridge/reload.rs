@@ -0,0 +1,26 @@
+use super::super::{RunCommand, SlashCommandResponse};
+use crate::{discord, payloads::command::MinecraftCommand};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::id::{marker::UserMarker, Id};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "reload",
+    desc = "Reloads the config without restarting the bridge"
+)]
+pub struct ReloadCommand;
+
+impl RunCommand for ReloadCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, _user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
+        Err(match discord::reload() {
+            Ok(()) => SlashCommandResponse::Success("Reloaded the config".to_string()),
+            Err(e) => SlashCommandResponse::Failure(format!("Couldn't reload the config: {e}")),
+        })
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+}
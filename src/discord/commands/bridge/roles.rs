@@ -0,0 +1,39 @@
+use super::super::{
+    guild::list::{collect_roster, read_roster},
+    RunCommand, SlashCommandResponse,
+};
+use crate::{
+    discord::{feedback::Collect, roles},
+    payloads::{command::MinecraftCommand, events::RawChatEvent},
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::id::{marker::UserMarker, Id};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "roles",
+    desc = "Shows the roles which would change to match everyone's guild rank"
+)]
+pub struct RolesCommand;
+
+impl RunCommand for RolesCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, _user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
+        Ok(MinecraftCommand::List)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        collect_roster(event)
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> SlashCommandResponse {
+        read_roster(&events, |_| {
+            SlashCommandResponse::Embed(Box::new(roles::report(&roles::plan())))
+        })
+    }
+}
@@ -1,13 +1,17 @@
 use super::{RunCommand, SlashCommandResponse};
 use crate::{
     bridge::Chat,
+    discord::feedback::Collect,
     payloads::{
         command::MinecraftCommand,
         events::{ChatEvent, RawChatEvent},
     },
 };
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_model::guild::Permissions;
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
 use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
 
 #[derive(CommandModel, CreateCommand)]
@@ -31,48 +35,85 @@ fn permissions() -> Permissions {
 impl RunCommand for ExecuteCommand {
     type Response = SlashCommandResponse;
 
-    fn get_command(&self) -> Result<MinecraftCommand, SlashCommandResponse> {
+    fn get_command(&self, _user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
         Ok(MinecraftCommand::Execute(self.get_command().to_string()))
     }
 
-    fn check_event(&self, event: RawChatEvent) -> Option<Self::Response> {
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        // There's no way to know what the output will look like, so take everything but chat until it goes quiet
+        if event.is_chat() {
+            Collect::Ignore
+        } else {
+            Collect::Line
+        }
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> Self::Response {
         use SlashCommandResponse::*;
 
-        let parsed = event.as_chat_event();
+        let output = events
+            .iter()
+            .map(|event| event.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
 
-        Some(Embed(Box::new(
+        let parsed = events
+            .iter()
+            .map(|event| {
+                let parsed = event.as_chat_event();
+
+                format!(
+                    "{event_type}: {parsed}",
+                    event_type = match parsed {
+                        ChatEvent::Message(ref msg) => match msg.chat {
+                            Chat::Guild => "Guild Message",
+                            Chat::Officer => "Officer Message",
+                            Chat::Party => "Party Message",
+                        },
+                        ChatEvent::Whisper(_) => "Whisper",
+                        ChatEvent::Toggle(_) => "Member Toggle",
+                        ChatEvent::GuildEvent(_) => "Guild Event",
+                        ChatEvent::Moderation(_) => "Moderation",
+                        ChatEvent::CommandResponse(_) => "Command Response",
+                        ChatEvent::Unknown(_) => "Unknown",
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Embed(Box::new(
             EmbedBuilder::new()
                 .description(format!(
                     "Executing `/{command}`",
                     command = self.get_command()
                 ))
-                .field(EmbedFieldBuilder::new(
-                    "Output",
-                    format!("```{raw}```", raw = *event),
-                ))
-                .field(EmbedFieldBuilder::new(
-                    format!(
-                        "Parsed as {event_type}",
-                        event_type = match parsed {
-                            ChatEvent::Message(ref msg) => match msg.chat {
-                                Chat::Guild => "Guild Message",
-                                Chat::Officer => "Officer Message",
-                            },
-                            ChatEvent::Toggle(_) => "Member Toggle",
-                            ChatEvent::GuildEvent(_) => "Guild Event",
-                            ChatEvent::Moderation(_) => "Moderation",
-                            ChatEvent::CommandResponse(_) => "Command Response",
-                            ChatEvent::Unknown(_) => "Unknown",
-                        }
-                    ),
-                    format!("```{parsed}```"),
-                ))
-                .color(super::colours::GREEN)
+                .field(EmbedFieldBuilder::new("Output", code_block(&output)))
+                .field(EmbedFieldBuilder::new("Parsed as", code_block(&parsed)))
+                .color(super::colours::green())
                 .build(),
-        )))
+        ))
     }
 }
 
+/// Wrap the text in a code block, cutting it short to fit in an embed field
+fn code_block(text: &str) -> String {
+    const MAX_LENGTH: usize = 1024 - "``````".len();
+
+    if text.chars().count() <= MAX_LENGTH {
+        return format!("```{text}```");
+    }
+
+    format!(
+        "```{text}\n…```",
+        text = text.chars().take(MAX_LENGTH - 2).collect::<String>()
+    )
+}
+
 impl ExecuteCommand {
     fn get_command(&self) -> &str {
         self.command.trim_start_matches('/').trim()
@@ -81,7 +122,7 @@ impl ExecuteCommand {
 
 #[cfg(test)]
 mod tests {
-    use super::super::testing::test_command;
+    use super::super::testing::{test_command, test_streaming_command};
     use super::*;
 
     #[test]
@@ -94,4 +135,51 @@ mod tests {
         )
         .is_embed())
     }
+
+    #[test]
+    fn multiple_lines() {
+        let SlashCommandResponse::Embed(embed) = test_streaming_command(
+            ExecuteCommand {
+                command: "g online".to_string(),
+            },
+            &["Guild Name: Bridge", "Online Members: 2"],
+        ) else {
+            panic!("Expected embed")
+        };
+
+        assert_eq!(
+            embed.fields[0].value,
+            "```Guild Name: Bridge\nOnline Members: 2```"
+        );
+    }
+
+    #[test]
+    fn chat_ignored() {
+        let SlashCommandResponse::Embed(embed) = test_streaming_command(
+            ExecuteCommand {
+                command: "g online".to_string(),
+            },
+            &["Guild > neyoa: Online Members: 1", "Guild Name: Bridge"],
+        ) else {
+            panic!("Expected embed")
+        };
+
+        assert_eq!(embed.fields[0].value, "```Guild Name: Bridge```");
+    }
+
+    #[test]
+    fn long_output() {
+        let output: &'static str = "a".repeat(2000).leak();
+
+        let SlashCommandResponse::Embed(embed) = test_streaming_command(
+            ExecuteCommand {
+                command: "g list".to_string(),
+            },
+            &[output],
+        ) else {
+            panic!("Expected embed")
+        };
+
+        assert_eq!(embed.fields[0].value.chars().count(), 1024);
+    }
 }
@@ -1,14 +1,20 @@
 mod demote;
 mod invite;
 mod kick;
+pub(super) mod list;
 mod mute;
+mod online;
 mod promote;
 mod setrank;
 mod unmute;
 
 use super::{RunCommand, SlashCommandResponse};
-use crate::payloads::{command::MinecraftCommand, events::RawChatEvent};
+use crate::{
+    discord::feedback::Collect,
+    payloads::{command::MinecraftCommand, events::RawChatEvent},
+};
 use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::id::{marker::UserMarker, Id};
 
 #[derive(CommandModel, CreateCommand)]
 #[command(name = "guild", desc = "Guild commands")]
@@ -33,6 +39,12 @@ pub enum GuildCommand {
 
     #[command(name = "setrank")]
     SetRank(setrank::SetRankCommand),
+
+    #[command(name = "online")]
+    Online(online::OnlineCommand),
+
+    #[command(name = "list")]
+    List(list::ListCommand),
 }
 
 impl GuildCommand {
@@ -45,6 +57,8 @@ impl GuildCommand {
             Self::Promote(command) => command,
             Self::Demote(command) => command,
             Self::SetRank(command) => command,
+            Self::Online(command) => command,
+            Self::List(command) => command,
         }
     }
 }
@@ -52,11 +66,23 @@ impl GuildCommand {
 impl RunCommand for GuildCommand {
     type Response = SlashCommandResponse;
 
-    fn get_command(&self) -> Result<MinecraftCommand, Self::Response> {
-        self.as_run_command().get_command()
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, Self::Response> {
+        self.as_run_command().get_command(user)
     }
 
     fn check_event(&self, event: RawChatEvent) -> Option<Self::Response> {
         self.as_run_command().check_event(event)
     }
+
+    fn is_streaming(&self) -> bool {
+        self.as_run_command().is_streaming()
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        self.as_run_command().collect_event(event)
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> Self::Response {
+        self.as_run_command().finish(events)
+    }
 }
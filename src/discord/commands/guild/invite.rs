@@ -8,7 +8,10 @@ use crate::{
 };
 use lazy_regex::regex_captures;
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_model::guild::Permissions;
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
 
 #[derive(CommandModel, CreateCommand)]
 #[command(
@@ -31,7 +34,10 @@ fn permissions() -> Permissions {
 impl RunCommand for InviteCommand {
     type Response = SlashCommandResponse;
 
-    fn get_command(&self) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
+    fn get_command(
+        &self,
+        _user: Id<UserMarker>,
+    ) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
         let Ok(player) = ValidIGN::try_from(self.player.as_str()) else {
             return Err(SlashCommandResponse::Failure(format!(
                 "`{ign}` is not a valid IGN",
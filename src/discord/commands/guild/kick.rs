@@ -8,7 +8,10 @@ use crate::{
     sanitizer::{CleanString, ValidIGN},
 };
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_model::guild::Permissions;
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
 
 #[derive(CommandModel, CreateCommand)]
 #[command(
@@ -34,7 +37,10 @@ fn permissions() -> Permissions {
 impl RunCommand for KickCommand {
     type Response = SlashCommandResponse;
 
-    fn get_command(&self) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
+    fn get_command(
+        &self,
+        _user: Id<UserMarker>,
+    ) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
         let Ok(player) = ValidIGN::try_from(self.player.as_str()) else {
             return Err(SlashCommandResponse::Failure(format!(
                 "`{ign}` is not a valid IGN",
@@ -0,0 +1,245 @@
+use super::{
+    super::{RunCommand, SlashCommandResponse},
+    online::{collect_sections, field_value},
+};
+use crate::{
+    discord::{autocomplete, colours, feedback::Collect, roles},
+    payloads::{
+        command::MinecraftCommand,
+        events::{GuildRoster, RawChatEvent, Response},
+    },
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::Embed,
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+/// The most members shown on a single page
+const PAGE_SIZE: usize = 40;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "list",
+    desc = "Lists every guild member",
+    default_permissions = "permissions",
+    dm_permission = false
+)]
+pub struct ListCommand;
+
+fn permissions() -> Permissions {
+    Permissions::empty()
+}
+
+impl RunCommand for ListCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(
+        &self,
+        _user: Id<UserMarker>,
+    ) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
+        Ok(MinecraftCommand::List)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        collect_roster(event)
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> SlashCommandResponse {
+        read_roster(&events, |roster| SlashCommandResponse::Pages(pages(roster)))
+    }
+}
+
+/// Collect the lines of the response to `/g list`
+pub(in crate::discord::commands) fn collect_roster(event: &RawChatEvent) -> Collect {
+    collect_sections(event, "Total Members")
+}
+
+/// Parse the collected response to `/g list`, updating the autocomplete and ranks before responding with the roster
+pub(in crate::discord::commands) fn read_roster(
+    events: &[RawChatEvent],
+    respond: impl FnOnce(&GuildRoster) -> SlashCommandResponse,
+) -> SlashCommandResponse {
+    use SlashCommandResponse::*;
+
+    let output = events
+        .iter()
+        .map(|event| event.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let Ok(roster) = GuildRoster::try_from(output.as_str()) else {
+        return match Response::try_from(output.as_str()) {
+            Ok(response) => Failure(response.to_string()),
+            Err(_) => Failure("Couldn't read the list of guild members".to_string()),
+        };
+    };
+
+    // A line lost to chat would otherwise drop members from the autocomplete and their roles
+    if roster.members().count() == roster.total {
+        autocomplete::replace_usernames(roster.members());
+        roles::replace_ranks(&roster);
+    }
+
+    respond(&roster)
+}
+
+/// Split the roster into pages of at most [`PAGE_SIZE`] members, continuing a rank onto the next page if needed
+fn pages(roster: &GuildRoster) -> Vec<Embed> {
+    let mut pages: Vec<Vec<EmbedFieldBuilder>> = vec![Vec::new()];
+    let mut space = PAGE_SIZE;
+
+    for section in roster
+        .ranks
+        .iter()
+        .filter(|section| !section.members.is_empty())
+    {
+        let mut remaining = section.members.as_slice();
+
+        while !remaining.is_empty() {
+            if space == 0 {
+                pages.push(Vec::new());
+                space = PAGE_SIZE;
+            }
+
+            let name = if remaining.len() == section.members.len() {
+                format!(
+                    "{rank} ({count})",
+                    rank = section.rank,
+                    count = section.members.len()
+                )
+            } else {
+                format!("{rank} (continued)", rank = section.rank)
+            };
+            let (chunk, rest) = remaining.split_at(remaining.len().min(space));
+
+            pages
+                .last_mut()
+                .expect("There is always at least one page")
+                .push(EmbedFieldBuilder::new(name, field_value(chunk)));
+            space -= chunk.len();
+            remaining = rest;
+        }
+    }
+
+    let count = pages.len();
+
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, fields)| {
+            let mut embed = EmbedBuilder::new()
+                .title(match roster.name {
+                    Some(name) => format!("{name} Members"),
+                    None => "Guild Members".to_string(),
+                })
+                .footer(EmbedFooterBuilder::new(format!(
+                    "Page {page}/{count} • {total} members",
+                    page = index + 1,
+                    total = roster.total
+                )))
+                .color(colours::green());
+
+            for field in fields {
+                embed = embed.field(field);
+            }
+
+            embed.build()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::testing::{test_command, test_streaming_command};
+    use super::*;
+    use crate::discord::autocomplete;
+    use test_case::test_case;
+
+    #[test]
+    fn success() {
+        let SlashCommandResponse::Pages(pages) = test_streaming_command(
+            ListCommand,
+            &[
+                "-----------------------------------------------------\nGuild Name: Bridge",
+                "-- Guild Master --\n[MVP++] neyoa ●",
+                "Guild > neyoa: unrelated",
+                "-- Member --\n[VIP] neytwoa ●  alpha_bravo ●",
+                "Total Members: 3\n-----------------------------------------------------",
+            ],
+        ) else {
+            panic!("Expected pages")
+        };
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].title.as_deref(), Some("Bridge Members"));
+        assert_eq!(pages[0].fields[1].name, "Member (2)");
+        assert_eq!(pages[0].fields[1].value, "`neytwoa`, `alpha_bravo`");
+        assert!(autocomplete::get_matches("alpha_bravo")
+            .iter()
+            .any(|member| &**member == "alpha_bravo"));
+    }
+
+    #[test]
+    fn incomplete() {
+        test_streaming_command(
+            ListCommand,
+            &[
+                "Guild Name: Bridge",
+                "-- Guild Master --\n[MVP++] charlie_delta ●",
+                "Total Members: 2",
+            ],
+        );
+
+        assert!(!autocomplete::get_matches("charlie_delta")
+            .iter()
+            .any(|member| &**member == "charlie_delta"));
+    }
+
+    #[test_case("Guild > neyoa: -- Guild Master --" ; "Guild chat")]
+    #[test_case("From neyoa: Total Members: 1" ; "Whisper")]
+    #[test_case("Total Members: 1 -- Member --" ; "Unanchored")]
+    fn ignored(message: &str) {
+        assert_eq!(
+            collect_roster(&RawChatEvent(message.to_string())),
+            Collect::Ignore
+        );
+    }
+
+    #[test]
+    fn many_pages() {
+        crate::config::init_for_tests();
+
+        let members = (0..100).map(|n| format!("member{n}")).collect::<Vec<_>>();
+        let roster = GuildRoster::try_from(
+            format!(
+                "Guild Name: Bridge\n-- Guild Master --\nneyoa ●\n-- Member --\n{members}\nTotal Members: 101",
+                members = members.join(" ●  ") + " ●"
+            )
+            .as_str(),
+        )
+        .unwrap();
+
+        let pages = pages(&roster);
+
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].fields[1].name, "Member (100)");
+        assert_eq!(pages[1].fields[0].name, "Member (continued)");
+        assert_eq!(
+            pages[2].footer.as_ref().unwrap().text,
+            "Page 3/3 • 101 members"
+        );
+    }
+
+    #[test_case("You must be in a guild to use this command!" ; "Bot not in a guild")]
+    #[test_case("You do not have permission to use this command!" ; "No permission")]
+    fn failures(message: &'static str) {
+        assert!(test_command(ListCommand, message).is_failure());
+    }
+}
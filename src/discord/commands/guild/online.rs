@@ -0,0 +1,199 @@
+use super::super::{RunCommand, SlashCommandResponse};
+use crate::{
+    discord::{colours, feedback::Collect},
+    payloads::{
+        command::MinecraftCommand,
+        events::{GuildOnline, RawChatEvent, Response},
+    },
+};
+use lazy_regex::{regex_captures, regex_is_match};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "online",
+    desc = "Lists the guild members who are online",
+    default_permissions = "permissions",
+    dm_permission = false
+)]
+pub struct OnlineCommand;
+
+fn permissions() -> Permissions {
+    Permissions::empty()
+}
+
+impl RunCommand for OnlineCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(
+        &self,
+        _user: Id<UserMarker>,
+    ) -> crate::Result<MinecraftCommand, SlashCommandResponse> {
+        Ok(MinecraftCommand::Online)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn collect_event(&self, event: &RawChatEvent) -> Collect {
+        collect_sections(event, "Online Members")
+    }
+
+    fn finish(&self, events: Vec<RawChatEvent>) -> SlashCommandResponse {
+        use SlashCommandResponse::*;
+
+        let output = events
+            .iter()
+            .map(|event| event.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let Ok(online) = GuildOnline::try_from(output.as_str()) else {
+            return match Response::try_from(output.as_str()) {
+                Ok(response) => Failure(response.to_string()),
+                Err(_) => Failure("Couldn't read the list of online members".to_string()),
+            };
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title(match online.name {
+                Some(name) => format!("{name} Online Members"),
+                None => "Online Members".to_string(),
+            })
+            .footer(EmbedFooterBuilder::new(format!(
+                "{count} online",
+                count = online.online
+            )))
+            .color(colours::green());
+
+        for section in online
+            .ranks
+            .iter()
+            .filter(|section| !section.members.is_empty())
+        {
+            embed = embed.field(EmbedFieldBuilder::new(
+                format!(
+                    "{rank} ({count})",
+                    rank = section.rank,
+                    count = section.members.len()
+                ),
+                field_value(&section.members),
+            ));
+        }
+
+        Embed(Box::new(embed.build()))
+    }
+}
+
+/// Collect the lines of a guild listing split into ranks, which ends with a count such as `Online Members: 3`
+pub(super) fn collect_sections(event: &RawChatEvent, count: &str) -> Collect {
+    // Players can type anything the listing contains
+    if event.is_chat() {
+        return Collect::Ignore;
+    }
+
+    let mut lines = event.lines().map(str::trim);
+
+    if lines.clone().any(|line| {
+        regex_captures!(r#"^([\w ]+ Members): \d+$"#, line).is_some_and(|(_, label)| label == count)
+    }) {
+        return Collect::Last;
+    }
+
+    if lines.any(|line| {
+        regex_is_match!(r#"^Guild Name: .+$"#, line)
+            || regex_is_match!(r#"^-- .+ --$"#, line)
+            || regex_is_match!(r#"^(?:(?:\[[\w+]+\] )?\w+ ●\s*)+$"#, line)
+    }) {
+        return Collect::Line;
+    }
+
+    match Response::try_from(event.as_str()) {
+        Ok(Response::NoPermission | Response::BotNotInGuild) => Collect::Last,
+        _ => Collect::Ignore,
+    }
+}
+
+/// List the members, leaving out as many as needed to fit in an embed field
+pub(super) fn field_value(members: &[&str]) -> String {
+    const MAX_LENGTH: usize = 1024;
+
+    let mut value = String::new();
+
+    for (index, member) in members.iter().enumerate() {
+        let next = match index {
+            0 => format!("`{member}`"),
+            _ => format!(", `{member}`"),
+        };
+        let remaining = format!(" and {count} more", count = members.len() - index);
+
+        if value.len() + next.len() + remaining.len() > MAX_LENGTH {
+            value.push_str(&remaining);
+            break;
+        }
+
+        value.push_str(&next);
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::testing::{test_command, test_streaming_command};
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn success() {
+        let SlashCommandResponse::Embed(embed) = test_streaming_command(
+            OnlineCommand,
+            &[
+                "-----------------------------------------------------\nGuild Name: Bridge",
+                "Guild > neyoa: unrelated",
+                "-- Guild Master --\n[MVP++] neyoa ●",
+                "-- Member --\n[VIP] neytwoa ●  alpha_bravo ●",
+                "Online Members: 3\n-----------------------------------------------------",
+            ],
+        ) else {
+            panic!("Expected embed")
+        };
+
+        assert_eq!(embed.title.as_deref(), Some("Bridge Online Members"));
+        assert_eq!(embed.fields.len(), 2);
+        assert_eq!(embed.fields[1].name, "Member (2)");
+        assert_eq!(embed.fields[1].value, "`neytwoa`, `alpha_bravo`");
+    }
+
+    #[test_case("You must be in a guild to use this command!" ; "Bot not in a guild")]
+    #[test_case("You do not have permission to use this command!" ; "No permission")]
+    fn failures(message: &'static str) {
+        assert!(test_command(OnlineCommand, message).is_failure());
+    }
+
+    #[test_case("Guild > neyoa: Online Members: 1" ; "Guild chat")]
+    #[test_case("To neyoa: -- Member --" ; "Whisper")]
+    #[test_case("Total Members: 1" ; "Other count")]
+    #[test_case("neyoa left. ●" ; "Unanchored")]
+    fn ignored(message: &str) {
+        assert_eq!(
+            OnlineCommand.collect_event(&RawChatEvent(message.to_string())),
+            Collect::Ignore
+        );
+    }
+
+    #[test]
+    fn long_field() {
+        let members = vec!["abcdefghijklmnop"; 100];
+        let value = field_value(&members);
+
+        assert!(value.len() <= 1024);
+        assert!(value.ends_with("more"));
+    }
+}
@@ -2,11 +2,17 @@ use super::{RunCommand, SlashCommandResponse};
 use crate::{
     config,
     discord::{avatar_url, colours, reactions::Reaction},
+    minecraft,
     payloads::command::MinecraftCommand,
+    storage::{self, Counter},
 };
 use strum::IntoEnumIterator;
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_model::{channel::message::embed::EmbedField, guild::Permissions};
+use twilight_model::{
+    channel::message::embed::EmbedField,
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
 use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder, ImageSource};
 
 #[derive(CommandModel, CreateCommand)]
@@ -25,13 +31,19 @@ fn permissions() -> Permissions {
 impl RunCommand for HelpCommand {
     type Response = SlashCommandResponse;
 
-    fn get_command(&self) -> Result<MinecraftCommand, SlashCommandResponse> {
+    fn get_command(&self, _user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
         let help_embed = EmbedBuilder::new()
             .title("Bridge Help")
             .field(EmbedField {
                 name: "Reactions".to_string(),
                 value: Reaction::iter()
-                    .map(|reaction| format!("`{}`: {}", reaction.emoji(), reaction.description()))
+                    .filter_map(|reaction| {
+                        Some(format!(
+                            "`{}`: {}",
+                            reaction.emoji()?,
+                            reaction.description()
+                        ))
+                    })
                     .collect::<Vec<_>>()
                     .join("\n"),
                 inline: false,
@@ -42,6 +54,12 @@ impl RunCommand for HelpCommand {
                 value: [
                     format!("Guild Channel: <#{}>", config().channels.guild),
                     format!("Officer Channel: <#{}>", config().channels.officer),
+                    format!("Location: `{}`", minecraft::current_location()),
+                    format!(
+                        "Messages Bridged: `{}` from Minecraft, `{}` from Discord",
+                        storage::count(Counter::FromMinecraft),
+                        storage::count(Counter::FromDiscord)
+                    ),
                     format!("Version: `{}`", env!("CARGO_PKG_VERSION")),
                 ]
                 .join("\n"),
@@ -51,7 +69,7 @@ impl RunCommand for HelpCommand {
                 EmbedFooterBuilder::new("Created by neyoa")
                     .icon_url(ImageSource::url(avatar_url("neyoa")).expect("Invalid image url")),
             )
-            .color(colours::GREEN)
+            .color(colours::green())
             .build();
 
         Err(SlashCommandResponse::Embed(Box::new(help_embed)))
@@ -64,17 +82,6 @@ mod tests {
 
     #[test]
     fn help() {
-        config::init(config::Config {
-            discord_token: String::new(),
-            email: None,
-            server_address: String::new(),
-            server_port: 25565,
-            channels: config::Channels {
-                guild: 0,
-                officer: 0,
-            },
-        });
-
         assert!(test_command(HelpCommand, "").is_embed())
     }
 }
@@ -0,0 +1,41 @@
+mod search;
+
+use super::{RunCommand, SlashCommandResponse};
+use crate::payloads::command::MinecraftCommand;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "history",
+    desc = "Messages bridged between Minecraft and Discord",
+    default_permissions = "permissions",
+    dm_permission = false
+)]
+pub enum HistoryCommand {
+    #[command(name = "search")]
+    Search(search::SearchCommand),
+}
+
+fn permissions() -> Permissions {
+    Permissions::MODERATE_MEMBERS
+}
+
+impl HistoryCommand {
+    fn as_run_command(&self) -> &dyn RunCommand<Response = SlashCommandResponse> {
+        match self {
+            Self::Search(command) => command,
+        }
+    }
+}
+
+impl RunCommand for HistoryCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, Self::Response> {
+        self.as_run_command().get_command(user)
+    }
+}
@@ -0,0 +1,186 @@
+use super::super::{RunCommand, SlashCommandResponse};
+use crate::{
+    bridge::Chat,
+    discord::{can_view, colours},
+    payloads::command::MinecraftCommand,
+    storage::history::{self, Entry, Query, MAX_RESULTS},
+};
+use std::time::Duration;
+use twilight_interactions::command::{CommandModel, CommandOption, CreateCommand, CreateOption};
+use twilight_model::{
+    channel::message::Embed,
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+/// The most messages shown on a single page
+const PAGE_SIZE: usize = 10;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "search",
+    desc = "Searches messages bridged between Minecraft and Discord"
+)]
+pub struct SearchCommand {
+    /// Text the messages contain
+    #[command(min_length = 1, max_length = 256)]
+    text: String,
+
+    /// Only messages sent by this player
+    #[command(min_length = 1, max_length = 32, autocomplete = true)]
+    player: Option<String>,
+
+    /// How far back to search
+    since: Option<Since>,
+}
+
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy, PartialEq)]
+pub enum Since {
+    #[option(name = "Last hour", value = "hour")]
+    Hour,
+    #[option(name = "Last day", value = "day")]
+    Day,
+    #[option(name = "Last week", value = "week")]
+    Week,
+    #[option(name = "Last 30 days", value = "month")]
+    Month,
+}
+
+impl From<Since> for Duration {
+    fn from(value: Since) -> Self {
+        let hours = match value {
+            Since::Hour => 1,
+            Since::Day => 24,
+            Since::Week => 7 * 24,
+            Since::Month => 30 * 24,
+        };
+
+        Duration::from_secs(hours * 60 * 60)
+    }
+}
+
+impl RunCommand for SearchCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
+        let chats = visible_chats(|channel| can_view(user, channel));
+        let results = history::search(&Query {
+            text: &self.text,
+            player: self.player.as_deref(),
+            chats: &chats,
+            since: self.since.map(Duration::from),
+        });
+
+        Err(if results.is_empty() {
+            SlashCommandResponse::Failure(format!(
+                "No bridged messages contain `{text}`",
+                text = self.text
+            ))
+        } else {
+            SlashCommandResponse::Pages(pages(&results))
+        })
+    }
+}
+
+/// The chats whose bridged channel can be seen, so the history never shows more than Discord would
+fn visible_chats(can_view: impl Fn(Id<ChannelMarker>) -> bool) -> Vec<Chat> {
+    [Chat::Guild, Chat::Officer, Chat::Party]
+        .into_iter()
+        .filter(|chat| {
+            chat.channel()
+                .is_some_and(|channel| can_view(Id::new(channel)))
+        })
+        .collect()
+}
+
+/// Split the results into pages of at most [`PAGE_SIZE`] messages
+fn pages(results: &[Entry]) -> Vec<Embed> {
+    let count = results.len().div_ceil(PAGE_SIZE);
+
+    results
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            EmbedBuilder::new()
+                .title("Message History")
+                .description(chunk.iter().map(line).collect::<Vec<_>>().join("\n"))
+                .footer(EmbedFooterBuilder::new(format!(
+                    "Page {page}/{count} • {total}{newest} messages",
+                    page = index + 1,
+                    total = results.len(),
+                    newest = if results.len() == MAX_RESULTS {
+                        " newest"
+                    } else {
+                        ""
+                    }
+                )))
+                .color(colours::green())
+                .build()
+        })
+        .collect()
+}
+
+fn line(entry: &Entry) -> String {
+    format!(
+        "<t:{time}:f> {chat} `{author}`{from}: {content}",
+        time = entry.time,
+        chat = match entry.chat {
+            Chat::Guild => "Guild",
+            Chat::Officer => "Officer",
+            Chat::Party => "Party",
+        },
+        author = entry.author,
+        from = if entry.from_discord { " (Discord)" } else { "" },
+        content = entry.content
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(count: usize) -> Vec<Entry> {
+        (0..count)
+            .map(|n| Entry {
+                time: 1_700_000_000 + n as u64,
+                chat: Chat::Guild,
+                from_discord: n % 2 == 0,
+                author: "neyoa".to_string(),
+                content: format!("message {n}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn visible() {
+        crate::config::init_for_tests();
+
+        assert_eq!(visible_chats(|_| true), [Chat::Guild, Chat::Officer]);
+        assert_eq!(
+            visible_chats(|channel| channel != Id::new(2)),
+            [Chat::Guild]
+        );
+        assert!(visible_chats(|_| false).is_empty());
+    }
+
+    #[test]
+    fn paginated() {
+        crate::config::init_for_tests();
+
+        let pages = pages(&entries(25));
+
+        assert_eq!(pages.len(), 3);
+        assert_eq!(
+            pages[0].description.as_deref().unwrap().lines().next(),
+            Some("<t:1700000000:f> Guild `neyoa` (Discord): message 0")
+        );
+        assert_eq!(pages[2].description.as_deref().unwrap().lines().count(), 5);
+        assert_eq!(
+            pages[2].footer.as_ref().unwrap().text,
+            "Page 3/3 • 25 messages"
+        );
+    }
+}
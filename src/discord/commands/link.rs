@@ -0,0 +1,48 @@
+use super::{RunCommand, SlashCommandResponse};
+use crate::{
+    discord::links::{self, CODE_TIMEOUT},
+    payloads::command::MinecraftCommand,
+    sanitizer::ValidIGN,
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "link",
+    desc = "Links your Discord account to your Minecraft account",
+    default_permissions = "permissions",
+    dm_permission = true
+)]
+pub struct LinkCommand {
+    /// Your IGN
+    #[command(min_length = 1, max_length = 16, autocomplete = true)]
+    ign: String,
+}
+
+fn permissions() -> Permissions {
+    Permissions::empty()
+}
+
+impl RunCommand for LinkCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
+        let Ok(ign) = ValidIGN::try_from(self.ign.as_str()) else {
+            return Err(SlashCommandResponse::Failure(format!(
+                "`{ign}` is not a valid IGN",
+                ign = self.ign
+            )));
+        };
+
+        let code = links::start(user, ign.clone());
+
+        Err(SlashCommandResponse::Success(format!(
+            "Type `{code}` in guild chat or whisper it to me as `{ign}` within {minutes} minutes to link your account",
+            minutes = CODE_TIMEOUT.as_secs() / 60
+        )))
+    }
+}
@@ -0,0 +1,31 @@
+use super::{RunCommand, SlashCommandResponse};
+use crate::{discord::links, payloads::command::MinecraftCommand};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::UserMarker, Id},
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "unlink",
+    desc = "Unlinks your Discord account from your Minecraft account",
+    default_permissions = "permissions",
+    dm_permission = true
+)]
+pub struct UnlinkCommand;
+
+fn permissions() -> Permissions {
+    Permissions::empty()
+}
+
+impl RunCommand for UnlinkCommand {
+    type Response = SlashCommandResponse;
+
+    fn get_command(&self, user: Id<UserMarker>) -> Result<MinecraftCommand, SlashCommandResponse> {
+        Err(match links::unlink(user) {
+            Some(ign) => SlashCommandResponse::Success(format!("Unlinked you from `{ign}`")),
+            None => SlashCommandResponse::Failure("You aren't linked to an account".to_string()),
+        })
+    }
+}
@@ -0,0 +1,568 @@
+use crate::{
+    config,
+    payloads::{
+        command::{CommandPayload, MinecraftCommand, NotSent},
+        events::{RawChatEvent, Response},
+    },
+};
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc,
+        oneshot::{self, error::TryRecvError},
+    },
+    time::Instant,
+};
+
+/// How long to wait for another event before finishing a response which spans several events
+pub const QUIET_WINDOW: Duration = Duration::from_secs(1);
+
+/// How an event relates to a response spanning several events
+#[derive(Debug, PartialEq)]
+pub enum Collect {
+    /// The event isn't part of the response
+    Ignore,
+    /// The event is part of the response, and more may follow
+    Line,
+    /// The event is the last part of the response
+    Last,
+}
+
+/// Sends commands to Minecraft and matches the chat events which follow to the commands they respond to.
+///
+/// Any number of commands can wait for a response at the same time, and every event is offered to all of them once they have been sent.
+/// When identical commands are waiting, only the oldest is offered events, so they are resolved in the order they were sent.
+/// Responses which could be for any command, such as missing permissions, only resolve the oldest command which accepts them.
+#[derive(Clone)]
+pub struct Feedback {
+    tx: mpsc::UnboundedSender<CommandPayload>,
+    waiters: Arc<Mutex<Waiters>>,
+}
+
+impl Feedback {
+    pub fn new(
+        tx: mpsc::UnboundedSender<CommandPayload>,
+        mut rx: async_broadcast::Receiver<RawChatEvent>,
+    ) -> Self {
+        let waiters = Arc::new(Mutex::new(Waiters::default()));
+
+        {
+            let waiters = waiters.clone();
+            tokio::spawn(async move {
+                while let Ok(event) = rx.recv().await {
+                    waiters.lock().dispatch(&event);
+                }
+
+                tracing::error!("Feedback receive channel closed");
+            });
+        }
+
+        Self { tx, waiters }
+    }
+
+    /// Send a command to Minecraft, and wait up to the configured timeout after it has been sent for `f` to find a response
+    pub async fn execute<F, R>(&self, command: MinecraftCommand, f: F) -> Option<R>
+    where
+        F: Fn(RawChatEvent) -> Option<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.execute_cancellable(command, f, std::future::pending(), || {})
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Like [`Feedback::execute`], but the command is taken out of the queue if `cancel` finishes before it has been sent,
+    /// `on_sent` is called as soon as it has been, and a command which isn't sent says why
+    pub async fn execute_cancellable<F, R>(
+        &self,
+        command: MinecraftCommand,
+        f: F,
+        cancel: impl Future<Output = ()>,
+        on_sent: impl FnOnce(),
+    ) -> Result<Option<R>, Unsent>
+    where
+        F: Fn(RawChatEvent) -> Option<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (verify_tx, verify_rx) = oneshot::channel();
+        let (result_tx, result_rx) = oneshot::channel();
+
+        let mut result_tx = Some(result_tx);
+        let check: Check = Box::new(move |event| match f(event.clone()) {
+            Some(response) => {
+                if let Some(result_tx) = result_tx.take() {
+                    // The receiver is only gone if the command timed out, in which case nobody needs the response
+                    result_tx.send(response).ok();
+                }
+
+                true
+            }
+            None => false,
+        });
+
+        // Register before sending so a quick response can't be missed
+        let registration = Registration {
+            id: self
+                .waiters
+                .lock()
+                .register(command.clone(), check, verify_rx),
+            waiters: &self.waiters,
+        };
+
+        // Dropping the verifier tells Minecraft nobody is waiting for the command anymore
+        tokio::select! {
+            sent = self.send(registration.id, command, verify_tx) => sent.map_err(Unsent::NotSent)?,
+            _ = cancel => return Err(Unsent::Cancelled),
+        }
+        on_sent();
+
+        match tokio::time::timeout(config().discord.timeout, result_rx).await {
+            Ok(result) => Ok(Some(result.expect("Waiter was dropped without a response"))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Send a command to Minecraft, and collect every event `f` accepts until it finds the last one,
+    /// nothing more is accepted for [`QUIET_WINDOW`], or the configured timeout passes after the command was sent
+    ///
+    /// Returns `None` if nothing is accepted before the timeout
+    pub async fn execute_streaming<F>(
+        &self,
+        command: MinecraftCommand,
+        f: F,
+    ) -> Option<Vec<RawChatEvent>>
+    where
+        F: Fn(&RawChatEvent) -> Collect + Send + 'static,
+    {
+        let (verify_tx, verify_rx) = oneshot::channel();
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+
+        let check: Check = Box::new(move |event| {
+            let collect = f(event);
+
+            if collect != Collect::Ignore {
+                // The receiver is only gone if the command timed out, in which case nobody needs the response
+                lines_tx
+                    .send((event.clone(), collect == Collect::Last))
+                    .ok();
+            }
+
+            collect == Collect::Last
+        });
+
+        let registration = Registration {
+            id: self
+                .waiters
+                .lock()
+                .register(command.clone(), check, verify_rx),
+            waiters: &self.waiters,
+        };
+
+        self.send(registration.id, command, verify_tx).await.ok()?;
+
+        let mut lines = vec![];
+        // Steady chat could otherwise keep a response going past when it can be shown
+        let deadline = Instant::now() + config().discord.timeout;
+        let mut until = deadline;
+
+        while let Ok(Some((line, last))) = tokio::time::timeout_at(until, lines_rx.recv()).await {
+            lines.push(line);
+
+            if last {
+                break;
+            }
+
+            until = deadline.min(Instant::now() + QUIET_WINDOW);
+        }
+
+        (!lines.is_empty()).then_some(lines)
+    }
+
+    /// Queue a command in Minecraft, waiting until it's sent or Minecraft says why it won't be
+    async fn send(
+        &self,
+        id: u64,
+        command: MinecraftCommand,
+        verify_tx: oneshot::Sender<Result<(), NotSent>>,
+    ) -> Result<(), NotSent> {
+        self.tx
+            .send(CommandPayload::new(command, verify_tx))
+            .expect("Minecraft payload receiver was dropped");
+
+        std::future::poll_fn(|cx| self.waiters.lock().poll_sent(id, cx)).await
+    }
+}
+
+/// Why a command never reached Minecraft
+#[derive(Debug, PartialEq)]
+pub enum Unsent {
+    /// The command was cancelled before it was sent
+    Cancelled,
+    /// Minecraft wouldn't send the command
+    NotSent(NotSent),
+}
+
+type Check = Box<dyn FnMut(&RawChatEvent) -> bool + Send>;
+
+struct Waiter {
+    id: u64,
+    command: MinecraftCommand,
+    /// Returns `true` if the event finishes the command's response
+    check: Check,
+    verifier: Verifier,
+}
+
+/// Whether Minecraft has sent a command yet, as nothing before then can be a response to it
+enum Verifier {
+    Waiting(oneshot::Receiver<Result<(), NotSent>>),
+    /// Whether Minecraft sent the command
+    Done(Result<(), NotSent>),
+}
+
+impl Verifier {
+    /// Check whether the command has been sent, without waiting
+    fn is_sent(&mut self) -> bool {
+        if let Verifier::Waiting(rx) = self {
+            match rx.try_recv() {
+                Ok(sent) => *self = Verifier::Done(sent),
+                Err(TryRecvError::Closed) => *self = Verifier::Done(Err(NotSent::Dropped)),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        matches!(self, Verifier::Done(Ok(())))
+    }
+
+    fn poll_sent(&mut self, cx: &mut Context) -> Poll<Result<(), NotSent>> {
+        match self {
+            Verifier::Waiting(rx) => {
+                let sent = ready!(Pin::new(rx).poll(cx)).unwrap_or(Err(NotSent::Dropped));
+                *self = Verifier::Done(sent);
+
+                Poll::Ready(sent)
+            }
+            Verifier::Done(sent) => Poll::Ready(*sent),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Waiters {
+    next_id: u64,
+    /// Ordered from oldest to newest
+    pending: Vec<Waiter>,
+}
+
+impl Waiters {
+    fn register(
+        &mut self,
+        command: MinecraftCommand,
+        check: Check,
+        verifier: oneshot::Receiver<Result<(), NotSent>>,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.pending.push(Waiter {
+            id,
+            command,
+            check,
+            verifier: Verifier::Waiting(verifier),
+        });
+
+        id
+    }
+
+    /// Whether Minecraft has sent the waiter's command, or why it never will
+    fn poll_sent(&mut self, id: u64, cx: &mut Context) -> Poll<Result<(), NotSent>> {
+        match self.pending.iter_mut().find(|waiter| waiter.id == id) {
+            Some(waiter) => waiter.verifier.poll_sent(cx),
+            // Waiters are only removed early once resolved, which needs the command to have been sent
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.pending.retain(|waiter| waiter.id != id);
+    }
+
+    /// Offer the event to every sent waiter which isn't behind an identical command, removing those it resolves
+    fn dispatch(&mut self, event: &RawChatEvent) {
+        // Responses which don't name a player could be for any command, so only one waiter can have them
+        let generic = matches!(
+            Response::try_from(event.as_str()),
+            Ok(Response::NoPermission | Response::CommandDisabled | Response::BotNotInGuild)
+        );
+        let mut resolved = vec![];
+
+        for index in 0..self.pending.len() {
+            let (older, newer) = self.pending.split_at_mut(index);
+            let waiter = &mut newer[0];
+
+            if !waiter.verifier.is_sent()
+                || older.iter().any(|other| other.command == waiter.command)
+            {
+                continue;
+            }
+
+            if (waiter.check)(event) {
+                resolved.push(waiter.id);
+
+                if generic {
+                    break;
+                }
+            }
+        }
+
+        self.pending.retain(|waiter| !resolved.contains(&waiter.id));
+    }
+}
+
+/// Removes the waiter when the command finishes, times out, or is cancelled
+struct Registration<'a> {
+    id: u64,
+    waiters: &'a Mutex<Waiters>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.waiters.lock().remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Register a waiter for a command which has already been sent
+    fn register(
+        waiters: &mut Waiters,
+        command: &str,
+        response: &'static str,
+    ) -> mpsc::UnboundedReceiver<&'static str> {
+        let (verify_tx, verify_rx) = oneshot::channel();
+        verify_tx.send(Ok(())).unwrap();
+
+        register_unsent(waiters, command, response, verify_rx)
+    }
+
+    fn register_unsent(
+        waiters: &mut Waiters,
+        command: &str,
+        response: &'static str,
+        verifier: oneshot::Receiver<Result<(), NotSent>>,
+    ) -> mpsc::UnboundedReceiver<&'static str> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        waiters.register(
+            MinecraftCommand::Execute(command.to_string()),
+            Box::new(move |event| {
+                let matched = **event == response;
+
+                if matched {
+                    tx.send(response).unwrap();
+                }
+
+                matched
+            }),
+            verifier,
+        );
+
+        rx
+    }
+
+    #[test]
+    fn concurrent() {
+        let mut waiters = Waiters::default();
+        let mut first = register(&mut waiters, "g online", "online");
+        let mut second = register(&mut waiters, "g list", "list");
+
+        waiters.dispatch(&RawChatEvent("list".to_string()));
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+
+        assert_eq!(first.try_recv(), Ok("online"));
+        assert_eq!(second.try_recv(), Ok("list"));
+        assert!(waiters.pending.is_empty());
+    }
+
+    #[test]
+    fn shared_response() {
+        let mut waiters = Waiters::default();
+        let mut first = register(&mut waiters, "g online", "neyoa joined.");
+        let mut second = register(&mut waiters, "g list", "neyoa joined.");
+
+        waiters.dispatch(&RawChatEvent("neyoa joined.".to_string()));
+
+        assert_eq!(first.try_recv(), Ok("neyoa joined."));
+        assert_eq!(second.try_recv(), Ok("neyoa joined."));
+    }
+
+    #[test]
+    fn generic_response() {
+        const NOT_IN_GUILD: &str = "You must be in a guild to use this command!";
+
+        let mut waiters = Waiters::default();
+        let mut first = register(&mut waiters, "g online", NOT_IN_GUILD);
+        let mut second = register(&mut waiters, "g list", NOT_IN_GUILD);
+
+        waiters.dispatch(&RawChatEvent(NOT_IN_GUILD.to_string()));
+
+        assert_eq!(first.try_recv(), Ok(NOT_IN_GUILD));
+        assert!(second.try_recv().is_err());
+    }
+
+    #[test]
+    fn not_sent() {
+        let mut waiters = Waiters::default();
+        let (verify_tx, verify_rx) = oneshot::channel();
+        let mut waiting = register_unsent(&mut waiters, "g online", "online", verify_rx);
+
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+        assert!(waiting.try_recv().is_err());
+
+        verify_tx.send(Ok(())).unwrap();
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+        assert_eq!(waiting.try_recv(), Ok("online"));
+    }
+
+    #[test]
+    fn identical_commands_resolve_in_order() {
+        let mut waiters = Waiters::default();
+        let mut first = register(&mut waiters, "g online", "online");
+        let mut second = register(&mut waiters, "g online", "online");
+
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+
+        assert_eq!(first.try_recv(), Ok("online"));
+        assert!(second.try_recv().is_err());
+
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+
+        assert_eq!(second.try_recv(), Ok("online"));
+    }
+
+    #[test]
+    fn removed() {
+        let mut waiters = Waiters::default();
+        let mut first = register(&mut waiters, "g online", "online");
+        let mut second = register(&mut waiters, "g online", "online");
+
+        waiters.remove(0);
+        waiters.dispatch(&RawChatEvent("online".to_string()));
+
+        assert!(first.try_recv().is_err());
+        assert_eq!(second.try_recv(), Ok("online"));
+    }
+
+    /// Start a [`Feedback`] whose commands are answered with `lines`
+    fn respond_with(lines: &'static [&'static str]) -> Feedback {
+        crate::config::init_for_tests();
+
+        let (to_minecraft, mut from_discord) = mpsc::unbounded_channel::<CommandPayload>();
+        let (to_discord, from_minecraft) = async_broadcast::broadcast(32);
+
+        tokio::spawn(async move {
+            let payload = from_discord.recv().await.unwrap();
+            payload.notify.lock().take().unwrap().send(Ok(())).unwrap();
+
+            for line in lines {
+                to_discord
+                    .broadcast(RawChatEvent(line.to_string()))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        Feedback::new(to_minecraft, from_minecraft)
+    }
+
+    #[tokio::test]
+    async fn cancelled() {
+        crate::config::init_for_tests();
+
+        let (to_minecraft, mut from_discord) = mpsc::unbounded_channel::<CommandPayload>();
+        let (_to_discord, from_minecraft) = async_broadcast::broadcast(32);
+
+        let result = Feedback::new(to_minecraft, from_minecraft)
+            .execute_cancellable(
+                MinecraftCommand::Execute("g online".to_string()),
+                |_| Some(()),
+                tokio::task::yield_now(),
+                || panic!("A cancelled command was sent"),
+            )
+            .await;
+
+        assert_eq!(result, Err(Unsent::Cancelled));
+
+        // Minecraft can tell that nobody is waiting for the command anymore
+        let payload = from_discord.recv().await.unwrap();
+        assert!(payload.notify.lock().as_ref().unwrap().is_closed());
+    }
+
+    #[tokio::test]
+    async fn too_long() {
+        crate::config::init_for_tests();
+
+        let (to_minecraft, mut from_discord) = mpsc::unbounded_channel::<CommandPayload>();
+        let (_to_discord, from_minecraft) = async_broadcast::broadcast(32);
+
+        tokio::spawn(async move {
+            let payload = from_discord.recv().await.unwrap();
+            let notify = payload.notify.lock().take().unwrap();
+            notify.send(Err(NotSent::TooLong)).unwrap();
+        });
+
+        let result = Feedback::new(to_minecraft, from_minecraft)
+            .execute_cancellable(
+                MinecraftCommand::Execute("g online".to_string()),
+                |_| Some(()),
+                std::future::pending(),
+                || panic!("A command which is too long was sent"),
+            )
+            .await;
+
+        assert_eq!(result, Err(Unsent::NotSent(NotSent::TooLong)));
+    }
+
+    fn collect(event: &RawChatEvent) -> Collect {
+        match event.as_str() {
+            "ignored" => Collect::Ignore,
+            "end" => Collect::Last,
+            _ => Collect::Line,
+        }
+    }
+
+    #[tokio::test]
+    async fn streaming_terminator() {
+        let lines = respond_with(&["start", "ignored", "middle", "end", "after"])
+            .execute_streaming(MinecraftCommand::Execute("g online".to_string()), collect)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            lines.iter().map(|line| line.as_str()).collect::<Vec<_>>(),
+            ["start", "middle", "end"]
+        );
+    }
+
+    #[tokio::test]
+    async fn streaming_quiet_window() {
+        let lines = respond_with(&["start", "middle"])
+            .execute_streaming(MinecraftCommand::Execute("g online".to_string()), collect)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            lines.iter().map(|line| line.as_str()).collect::<Vec<_>>(),
+            ["start", "middle"]
+        );
+    }
+}
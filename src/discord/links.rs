@@ -0,0 +1,181 @@
+use crate::{sanitizer::ValidIGN, storage};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant},
+};
+use twilight_model::id::{marker::UserMarker, Id};
+
+/// How long a player has to type their code before it expires
+pub const CODE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+static LINKS: Lazy<Mutex<Links>> = Lazy::new(|| Mutex::new(Links::load()));
+
+/// Discord users and the Minecraft accounts they have verified they own
+#[derive(Default)]
+struct Links {
+    linked: BTreeMap<Id<UserMarker>, ValidIGN>,
+    pending: Vec<Pending>,
+}
+
+/// A link waiting for the player to type its code in game
+struct Pending {
+    user: Id<UserMarker>,
+    ign: ValidIGN,
+    code: String,
+    expires: Instant,
+}
+
+impl Links {
+    fn load() -> Self {
+        Self {
+            linked: storage::read(|data| {
+                data.links
+                    .iter()
+                    .filter_map(|(user, ign)| {
+                        Some((
+                            Id::new_checked(user.parse().ok()?)?,
+                            ValidIGN::try_from(ign.as_str()).ok()?,
+                        ))
+                    })
+                    .collect()
+            }),
+            pending: Vec::new(),
+        }
+    }
+
+    fn save(&self) {
+        storage::write(|data| {
+            data.links = self
+                .linked
+                .iter()
+                .map(|(user, ign)| (user.to_string(), ign.to_string()))
+                .collect()
+        });
+    }
+
+    /// Start linking `user` to `ign`, replacing any link they already started
+    fn start(&mut self, user: Id<UserMarker>, ign: ValidIGN, now: Instant) -> String {
+        let code = format!(
+            "{:06}",
+            RandomState::new().build_hasher().finish() % 1_000_000
+        );
+
+        self.pending
+            .retain(|pending| pending.user != user && pending.expires > now);
+        self.pending.push(Pending {
+            user,
+            ign,
+            code: code.clone(),
+            expires: now + CODE_TIMEOUT,
+        });
+
+        code
+    }
+
+    /// Finish a pending link if `player` sent its code, returning the linked user
+    fn verify(&mut self, player: &str, content: &str, now: Instant) -> Option<Id<UserMarker>> {
+        let index = self.pending.iter().position(|pending| {
+            pending.expires > now
+                && pending.ign.eq_ignore_ascii_case(player)
+                && pending.code == content.trim()
+        })?;
+        let Pending { user, .. } = self.pending.swap_remove(index);
+
+        // Whoever typed the code has the right capitalisation of the name
+        let ign = ValidIGN::try_from(player).expect("Player names are always valid IGNs");
+        self.linked
+            .retain(|_, linked| !linked.eq_ignore_ascii_case(&ign));
+        self.linked.insert(user, ign);
+
+        Some(user)
+    }
+}
+
+/// Start linking `user` to `ign`, returning the code the player needs to type in game
+pub fn start(user: Id<UserMarker>, ign: ValidIGN) -> String {
+    LINKS.lock().start(user, ign, Instant::now())
+}
+
+/// Check whether a message from `player` finishes a pending link, returning the Discord user now linked to them
+pub fn verify(player: &str, content: &str) -> Option<Id<UserMarker>> {
+    let mut links = LINKS.lock();
+    let user = links.verify(player, content, Instant::now())?;
+    links.save();
+
+    Some(user)
+}
+
+/// Remove the user's link, returning the IGN they were linked to
+pub fn unlink(user: Id<UserMarker>) -> Option<ValidIGN> {
+    let mut links = LINKS.lock();
+    let ign = links.linked.remove(&user)?;
+    links.save();
+
+    Some(ign)
+}
+
+/// The IGN linked to a Discord user
+pub fn ign(user: Id<UserMarker>) -> Option<ValidIGN> {
+    LINKS.lock().linked.get(&user).cloned()
+}
+
+/// The Discord user linked to an IGN
+pub fn user(ign: &str) -> Option<Id<UserMarker>> {
+    LINKS
+        .lock()
+        .linked
+        .iter()
+        .find(|(_, linked)| linked.eq_ignore_ascii_case(ign))
+        .map(|(user, _)| *user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ign(ign: &str) -> ValidIGN {
+        ValidIGN::try_from(ign).unwrap()
+    }
+
+    #[test]
+    fn link() {
+        let mut links = Links::default();
+        let now = Instant::now();
+        let code = links.start(Id::new(1), ign("Neyoa"), now);
+
+        assert_eq!(links.verify("neyoa", "000000x", now), None);
+        assert_eq!(links.verify("alpha", &code, now), None);
+        assert_eq!(links.verify("neyoa", &code, now), Some(Id::new(1)));
+        assert_eq!(links.linked[&Id::new(1)], ign("neyoa"));
+
+        // Codes can only be used once
+        assert_eq!(links.verify("neyoa", &code, now), None);
+    }
+
+    #[test]
+    fn expired() {
+        let mut links = Links::default();
+        let now = Instant::now();
+        let code = links.start(Id::new(1), ign("neyoa"), now);
+
+        assert_eq!(links.verify("neyoa", &code, now + CODE_TIMEOUT), None);
+    }
+
+    #[test]
+    fn relink() {
+        let mut links = Links::default();
+        let now = Instant::now();
+
+        let code = links.start(Id::new(1), ign("neyoa"), now);
+        links.verify("neyoa", &code, now);
+        let code = links.start(Id::new(2), ign("neyoa"), now);
+        links.verify("neyoa", &code, now);
+
+        // An IGN can only be linked to one user at a time
+        assert_eq!(links.linked.len(), 1);
+        assert_eq!(links.linked[&Id::new(2)], ign("neyoa"));
+    }
+}
@@ -0,0 +1,216 @@
+use super::{colours, status};
+use crate::config;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::fmt::{Debug, Write};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    filter::filter_fn,
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+use twilight_model::id::Id;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+/// The most distinct lines posted at once, any more are counted and left out
+const MAX_LINES: usize = 10;
+/// The longest a single line can be, so every line fits in one embed
+const MAX_LINE_LENGTH: usize = 350;
+
+static BATCH: Lazy<Mutex<Batch>> = Lazy::new(|| Mutex::new(Batch::default()));
+
+/// Collects log lines at or above the configured level, to be posted to the log channel by [`run`]
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    DiscordLayer.with_filter(filter_fn(|metadata| {
+        // Failing to post logs would otherwise be posted too, looping forever
+        !metadata.target().starts_with(module_path!())
+            && config::try_config().is_some_and(|config| {
+                config.logger.channel.is_some() && *metadata.level() <= config.logger.level
+            })
+    }))
+}
+
+struct DiscordLayer;
+
+impl<S: Subscriber> Layer<S> for DiscordLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+
+        BATCH.lock().push(*event.metadata().level(), visitor.0);
+    }
+}
+
+/// Formats an event's message, followed by any other fields
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{value:?}"));
+        } else {
+            let _ = write!(self.0, " {name}={value:?}", name = field.name());
+        }
+    }
+}
+
+/// Log lines waiting to be posted
+#[derive(Default)]
+struct Batch {
+    lines: Vec<Line>,
+    /// Distinct lines left out because the batch was full
+    dropped: usize,
+}
+
+struct Line {
+    level: Level,
+    message: String,
+    /// How many times the line was logged
+    count: usize,
+}
+
+impl Batch {
+    /// Add a line, combining it with an identical one if it's already waiting
+    fn push(&mut self, level: Level, message: String) {
+        let message = truncate(message);
+
+        if let Some(line) = self
+            .lines
+            .iter_mut()
+            .find(|line| line.level == level && line.message == message)
+        {
+            line.count += 1;
+        } else if self.lines.len() < MAX_LINES {
+            self.lines.push(Line {
+                level,
+                message,
+                count: 1,
+            });
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    /// Take every waiting line, returning them as a code block along with the most severe level
+    fn take(&mut self) -> Option<(String, Level, usize)> {
+        let level = self.lines.iter().map(|line| line.level).min()?;
+        let lines = std::mem::take(&mut self.lines)
+            .into_iter()
+            .map(|line| {
+                format!(
+                    "{level:<5} {message}{repeated}",
+                    level = line.level,
+                    message = line.message,
+                    repeated = match line.count {
+                        1 => String::new(),
+                        count => format!(" (x{count})"),
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Some((
+            format!("```\n{lines}\n```", lines = lines.join("\n")),
+            level,
+            std::mem::take(&mut self.dropped),
+        ))
+    }
+}
+
+/// Shorten a line to [`MAX_LINE_LENGTH`] characters, without breaking out of the code block
+fn truncate(message: String) -> String {
+    let message = message.replace("```", "'''");
+
+    match message.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((index, _)) => format!("{}…", &message[..index]),
+        None => message,
+    }
+}
+
+/// Post the waiting log lines every interval, so an error loop only sends one message per interval
+pub async fn run() {
+    loop {
+        tokio::time::sleep(config().logger.interval).await;
+
+        let (Some(http), Some(channel)) = (status::HTTP.get(), config().logger.channel) else {
+            continue;
+        };
+        let Some((lines, level, dropped)) = BATCH.lock().take() else {
+            continue;
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .description(lines)
+            .timestamp(status::get_current_timestamp())
+            .color(if level == Level::ERROR {
+                colours::red()
+            } else {
+                colours::yellow()
+            });
+
+        if dropped > 0 {
+            embed = embed.footer(EmbedFooterBuilder::new(format!(
+                "{dropped} more lines left out"
+            )));
+        }
+
+        if let Err(err) = http
+            .create_message(Id::new(channel))
+            .embeds(&[embed.build()])
+            .expect("Invalid log embed")
+            .await
+        {
+            tracing::error!("Failed to post logs: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesce() {
+        let mut batch = Batch::default();
+        batch.push(Level::WARN, "Failed to execute webhook".to_string());
+        batch.push(Level::ERROR, "Disconnected".to_string());
+        batch.push(Level::WARN, "Failed to execute webhook".to_string());
+
+        let (lines, level, dropped) = batch.take().unwrap();
+
+        assert_eq!(
+            lines,
+            "```\nWARN  Failed to execute webhook (x2)\nERROR Disconnected\n```"
+        );
+        assert_eq!(level, Level::ERROR);
+        assert_eq!(dropped, 0);
+        assert!(batch.take().is_none());
+    }
+
+    #[test]
+    fn full() {
+        let mut batch = Batch::default();
+        for n in 0..MAX_LINES + 5 {
+            batch.push(Level::WARN, format!("line {n}"));
+        }
+
+        let (lines, _, dropped) = batch.take().unwrap();
+
+        assert_eq!(lines.lines().count(), MAX_LINES + 2);
+        assert_eq!(dropped, 5);
+    }
+
+    #[test]
+    fn long_line() {
+        let line = truncate("```".to_string() + &"a".repeat(MAX_LINE_LENGTH));
+
+        assert!(line.starts_with("'''"));
+        assert_eq!(line.chars().count(), MAX_LINE_LENGTH + 1);
+    }
+}
@@ -0,0 +1,88 @@
+use super::links;
+use lazy_regex::regex_replace_all;
+use twilight_cache_inmemory::InMemoryCache;
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+/// Replace each `@name` with a mention of the user `find` returns for it,
+/// returning the new content and every user mentioned
+pub fn replace(
+    content: &str,
+    find: impl Fn(&str) -> Option<Id<UserMarker>>,
+) -> (String, Vec<Id<UserMarker>>) {
+    let mut users = Vec::new();
+
+    let replaced = regex_replace_all!(r#"\B@(\w[\w.]*)"#, content, |mention: &str, name: &str| {
+        // Allow sentences to end straight after a mention
+        let name = name.trim_end_matches('.');
+
+        match find(name) {
+            Some(user) => {
+                if !users.contains(&user) {
+                    users.push(user);
+                }
+
+                format!("<@{user}>{rest}", rest = &mention[1 + name.len()..])
+            }
+            None => mention.to_string(),
+        }
+    });
+
+    (replaced.to_string(), users)
+}
+
+/// The member of the Discord server who linked `name` as their IGN, or has it as their nickname or username
+pub fn find_member(
+    cache: &InMemoryCache,
+    guild: Id<GuildMarker>,
+    name: &str,
+) -> Option<Id<UserMarker>> {
+    let members = cache.guild_members(guild)?;
+
+    if let Some(user) = links::user(name).filter(|user| members.contains(user)) {
+        return Some(user);
+    }
+
+    members.iter().copied().find(|user| {
+        let nick = cache
+            .member(guild, *user)
+            .and_then(|member| member.nick().map(str::to_string));
+        let username = cache.user(*user).map(|user| user.name.clone());
+
+        [nick, username]
+            .into_iter()
+            .flatten()
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn find(name: &str) -> Option<Id<UserMarker>> {
+        match name.to_lowercase().as_str() {
+            "neyoa" => Some(Id::new(1)),
+            "ney.two" => Some(Id::new(2)),
+            _ => None,
+        }
+    }
+
+    #[test_case("hi @neyoa", "hi <@1>", &[1] ; "Single")]
+    #[test_case("@NEYOA and @ney.two.", "<@1> and <@2>.", &[1, 2] ; "Several")]
+    #[test_case("@neyoa @neyoa", "<@1> <@1>", &[1] ; "Repeated")]
+    #[test_case("hi @someone", "hi @someone", &[] ; "Unknown")]
+    #[test_case("email@neyoa", "email@neyoa", &[] ; "Inside a word")]
+    fn mentions(content: &str, expected: &str, users: &[u64]) {
+        let (replaced, mentioned) = replace(content, find);
+
+        assert_eq!(replaced, expected);
+        assert_eq!(
+            mentioned,
+            users.iter().map(|user| Id::new(*user)).collect::<Vec<_>>()
+        );
+    }
+}
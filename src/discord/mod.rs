@@ -1,37 +1,69 @@
+mod audit;
 mod autocomplete;
 mod commands;
+mod feedback;
+mod links;
+pub mod logger;
+mod mentions;
+mod pagination;
 mod reactions;
 mod recv;
+mod roles;
 mod send;
 pub mod status;
 
 mod colours {
-    pub const GREEN: u32 = 0x47f04a;
-    pub const YELLOW: u32 = 0xff8c00;
-    pub const RED: u32 = 0xf04a47;
+    use crate::config;
+
+    pub fn green() -> u32 {
+        config().colours.green
+    }
+
+    pub fn yellow() -> u32 {
+        config().colours.yellow
+    }
+
+    pub fn red() -> u32 {
+        config().colours.red
+    }
 }
 
 use crate::{
+    config,
     payloads::{command::CommandPayload, events::RawChatEvent},
     Result,
 };
 pub use commands::TimeUnit;
+use once_cell::sync::OnceCell;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use twilight_cache_inmemory::{InMemoryCache, ResourceType};
-use twilight_gateway::{Config as ShardConfig, Intents, Shard, ShardId};
+use twilight_gateway::{Config as ShardConfig, Event, Intents, MessageSender, Shard, ShardId};
 use twilight_http::Client as HttpClient;
-use twilight_model::gateway::{
-    payload::outgoing::update_presence::UpdatePresencePayload,
-    presence::{MinimalActivity, Status},
+use twilight_model::{
+    gateway::{
+        payload::outgoing::{
+            update_presence::UpdatePresencePayload, RequestGuildMembers, UpdatePresence,
+        },
+        presence::{MinimalActivity, Status},
+        OpCode,
+    },
+    guild::Permissions,
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
 };
 use twilight_webhook::cache::WebhooksCache;
 
+/// Sends commands to the gateway from outside the shard's event loop, once it has started
+static GATEWAY: OnceCell<MessageSender> = OnceCell::new();
+
 pub struct Discord {
     sender: mpsc::UnboundedSender<CommandPayload>,
     receiver: async_broadcast::Receiver<RawChatEvent>,
     shard: Option<Shard>,
-    cache: InMemoryCache,
+    cache: Arc<InMemoryCache>,
     webhook_cache: WebhooksCache,
     pub http: Arc<HttpClient>,
 }
@@ -46,20 +78,7 @@ impl Discord {
         ),
     ) -> Self {
         let shard_config = ShardConfig::builder(token.to_string(), intents)
-            .presence(
-                UpdatePresencePayload::new(
-                    vec![MinimalActivity {
-                        kind: twilight_model::gateway::presence::ActivityType::Watching,
-                        name: "Guild Chat".to_string(),
-                        url: None,
-                    }
-                    .into()],
-                    false,
-                    None,
-                    Status::Online,
-                )
-                .expect("Presence payload contained no activities"),
-            )
+            .presence(presence())
             .build();
         let shard = Shard::with_config(ShardId::ONE, shard_config);
 
@@ -69,15 +88,28 @@ impl Discord {
             .set(http.clone())
             .expect("Status HTTP Client already set");
 
+        let cache = Arc::new(
+            InMemoryCache::builder()
+                .resource_types(
+                    ResourceType::GUILD
+                        | ResourceType::ROLE
+                        | ResourceType::CHANNEL
+                        | ResourceType::USER_CURRENT
+                        | ResourceType::MEMBER
+                        | ResourceType::USER,
+                )
+                .build(),
+        );
+
+        roles::CACHE
+            .set(cache.clone())
+            .expect("Role sync cache already set");
+
         Self {
             sender,
             receiver,
             shard: Some(shard),
-            cache: InMemoryCache::builder()
-                .resource_types(
-                    ResourceType::ROLE | ResourceType::CHANNEL | ResourceType::USER_CURRENT,
-                )
-                .build(),
+            cache,
             webhook_cache: WebhooksCache::new(),
             http,
         }
@@ -91,6 +123,10 @@ impl Discord {
         let mut shard = self.shard.take().expect("Shard was already taken");
         let discord = Arc::new(self);
 
+        GATEWAY
+            .set(shard.sender())
+            .expect("Gateway sender already set");
+
         // Handle events incoming from the Discord Gateway
         {
             let discord = discord.clone();
@@ -102,6 +138,18 @@ impl Discord {
 
                     match event {
                         Ok(event) => {
+                            // Members are needed to sync roles, and only some are sent with the guild
+                            if let Event::GuildCreate(guild) = &event {
+                                if let Err(error) = shard
+                                    .command(
+                                        &RequestGuildMembers::builder(guild.id).query("", None),
+                                    )
+                                    .await
+                                {
+                                    tracing::error!("Failed to request guild members: {error}");
+                                }
+                            }
+
                             let handler = handler.clone();
                             tokio::spawn(async move {
                                 handler.handle_discord_event(event).await;
@@ -115,6 +163,9 @@ impl Discord {
             });
         }
 
+        tokio::spawn(roles::run());
+        tokio::spawn(logger::run());
+
         let mut receiver = discord.receiver.clone();
 
         // Handle events incoming from Minecraft
@@ -131,7 +182,49 @@ impl Discord {
     }
 }
 
+/// Whether `user` can see `channel`, which is assumed not to be the case until they're cached
+fn can_view(user: Id<UserMarker>, channel: Id<ChannelMarker>) -> bool {
+    roles::CACHE
+        .get()
+        .and_then(|cache| cache.permissions().in_channel(user, channel).ok())
+        .is_some_and(|permissions| permissions.contains(Permissions::VIEW_CHANNEL))
+}
+
+/// The presence shown on the bot, from the config
+fn presence() -> UpdatePresencePayload {
+    UpdatePresencePayload::new(
+        vec![MinimalActivity {
+            kind: twilight_model::gateway::presence::ActivityType::Watching,
+            name: config().discord.presence.clone(),
+            url: None,
+        }
+        .into()],
+        false,
+        None,
+        Status::Online,
+    )
+    .expect("Presence payload contained no activities")
+}
+
+/// Reload the config (see [`config::reload`]), showing the new presence on the bot
+pub fn reload() -> Result<(), config::EnvError> {
+    config::reload()?;
+
+    if let Some(gateway) = GATEWAY.get() {
+        let update = UpdatePresence {
+            d: presence(),
+            op: OpCode::PresenceUpdate,
+        };
+
+        if let Err(error) = gateway.command(&update) {
+            tracing::error!("Failed to update the presence: {error}");
+        }
+    }
+
+    Ok(())
+}
+
 #[inline]
 pub fn avatar_url(ign: &str) -> String {
-    format!("https://mc-heads.net/avatar/{ign}/512")
+    config().discord.avatar_url.replace("{ign}", ign)
 }
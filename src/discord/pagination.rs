@@ -0,0 +1,164 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        Component, Embed,
+    },
+    id::{marker::InteractionMarker, Id},
+};
+
+/// How many paginated responses are kept before the oldest stop responding to their buttons
+const MAX_STORED: usize = 32;
+const PREFIX: &str = "page";
+
+static PAGES: Lazy<Mutex<VecDeque<(Id<InteractionMarker>, Arc<[Embed]>)>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_STORED)));
+
+/// Store the pages of an interaction's response, returning the first page and its buttons
+///
+/// # Panics
+/// Panics if `pages` is empty
+pub fn start(id: Id<InteractionMarker>, pages: Vec<Embed>) -> (Embed, Vec<Component>) {
+    let first = pages
+        .first()
+        .expect("Paginated response had no pages")
+        .clone();
+    let components = components(id, 0, pages.len());
+
+    if pages.len() > 1 {
+        let mut stored = PAGES.lock();
+        if stored.len() == MAX_STORED {
+            stored.pop_front();
+        }
+        stored.push_back((id, pages.into()));
+    }
+
+    (first, components)
+}
+
+/// Get the page (and its buttons) that a button's `custom_id` points to,
+/// or `None` if the response is no longer stored
+pub fn turn(custom_id: &str) -> Option<(Embed, Vec<Component>)> {
+    let (id, page) = parse(custom_id)?;
+    let pages = PAGES
+        .lock()
+        .iter()
+        .find(|(stored, _)| *stored == id)
+        .map(|(_, pages)| pages.clone())?;
+
+    Some((pages.get(page)?.clone(), components(id, page, pages.len())))
+}
+
+/// Whether a button's `custom_id` is one of the page buttons, even if its response is no longer stored
+pub fn is_page_button(custom_id: &str) -> bool {
+    custom_id.split(':').next() == Some(PREFIX)
+}
+
+fn parse(custom_id: &str) -> Option<(Id<InteractionMarker>, usize)> {
+    let mut parts = custom_id.split(':');
+
+    if parts.next()? != PREFIX {
+        return None;
+    }
+
+    let id = Id::new_checked(parts.next()?.parse().ok()?)?;
+    let page = parts.next()?.parse().ok()?;
+
+    Some((id, page))
+}
+
+fn components(id: Id<InteractionMarker>, page: usize, count: usize) -> Vec<Component> {
+    if count <= 1 {
+        return Vec::new();
+    }
+
+    let button = |label: &str, target: usize, disabled: bool| {
+        Component::Button(Button {
+            custom_id: Some(format!("{PREFIX}:{id}:{target}")),
+            disabled,
+            emoji: None,
+            label: Some(label.to_string()),
+            style: ButtonStyle::Secondary,
+            url: None,
+        })
+    };
+
+    vec![Component::ActionRow(ActionRow {
+        components: vec![
+            button("Previous", page.saturating_sub(1), page == 0),
+            button("Next", (page + 1).min(count - 1), page + 1 == count),
+        ],
+    })]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use twilight_util::builder::embed::EmbedBuilder;
+
+    fn pages(count: usize) -> Vec<Embed> {
+        (0..count)
+            .map(|page| EmbedBuilder::new().title(page.to_string()).build())
+            .collect()
+    }
+
+    fn custom_ids(components: &[Component]) -> Vec<(String, bool)> {
+        let [Component::ActionRow(row)] = components else {
+            panic!("Expected a single action row")
+        };
+
+        row.components
+            .iter()
+            .map(|component| match component {
+                Component::Button(button) => (button.custom_id.clone().unwrap(), button.disabled),
+                _ => panic!("Expected a button"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn single_page() {
+        let (embed, components) = start(Id::new(1), pages(1));
+
+        assert_eq!(embed.title.as_deref(), Some("0"));
+        assert!(components.is_empty());
+    }
+
+    #[test]
+    fn navigate() {
+        let (_, components) = start(Id::new(2), pages(3));
+        assert_eq!(
+            custom_ids(&components),
+            vec![
+                ("page:2:0".to_string(), true),
+                ("page:2:1".to_string(), false)
+            ]
+        );
+
+        let (embed, components) = turn("page:2:2").unwrap();
+        assert_eq!(embed.title.as_deref(), Some("2"));
+        assert_eq!(
+            custom_ids(&components),
+            vec![
+                ("page:2:1".to_string(), false),
+                ("page:2:2".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(turn("page:3:0").is_none());
+        assert!(turn("page:2:9").is_none());
+        assert!(turn("something:else").is_none());
+    }
+
+    #[test]
+    fn page_buttons() {
+        assert!(is_page_button("page:3:0"));
+        assert!(!is_page_button("pages:3:0"));
+        assert!(!is_page_button("something:else"));
+    }
+}
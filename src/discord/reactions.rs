@@ -1,9 +1,12 @@
+use crate::config;
 use strum::EnumIter;
 
 pub use Reaction::*;
 
 #[derive(Debug, EnumIter, PartialEq)]
 pub enum Reaction {
+    Delivered,
+    Pending,
     IllegalCharacters,
     TooLong,
     EmptyField,
@@ -11,12 +14,19 @@ pub enum Reaction {
     Muted,
     NoPermission,
     NotInGuild,
+    NotInParty,
+    PlayerOffline,
+    NoRecipient,
+    Disconnected,
     Warning,
 }
 
 impl Reaction {
-    pub fn emoji(&self) -> &'static str {
-        match self {
+    /// The emoji to react with, or `None` if the reaction is turned off
+    pub fn emoji(&self) -> Option<String> {
+        let emoji = match self {
+            Reaction::Delivered => return config().discord.success_reaction.clone(),
+            Reaction::Pending => return config().discord.pending_reaction.clone(),
             Reaction::IllegalCharacters => "✂️",
             Reaction::TooLong => "📏",
             Reaction::EmptyField => "❌",
@@ -24,12 +34,20 @@ impl Reaction {
             Reaction::Muted => "🔇",
             Reaction::NoPermission => "🔒",
             Reaction::NotInGuild => "⁉️",
+            Reaction::NotInParty => "🎉",
+            Reaction::PlayerOffline => "👻",
+            Reaction::NoRecipient => "📭",
+            Reaction::Disconnected => "🔌",
             Reaction::Warning => "⚠️",
-        }
+        };
+
+        Some(emoji.to_string())
     }
 
     pub fn description(&self) -> &'static str {
         match self {
+            Reaction::Delivered => "The message reached Minecraft",
+            Reaction::Pending => "The message is on its way to Minecraft",
             Reaction::IllegalCharacters => {
                 "The message or your nickname contains illegal characters"
             }
@@ -39,6 +57,10 @@ impl Reaction {
             Reaction::Muted => "I am currently muted ingame",
             Reaction::NoPermission => "I don't have permission to do that",
             Reaction::NotInGuild => "I am not in a guild",
+            Reaction::NotInParty => "I am not in a party",
+            Reaction::PlayerOffline => "The player you're whispering to isn't online",
+            Reaction::NoRecipient => "Reply to a whisper to choose who to whisper to",
+            Reaction::Disconnected => "I am not connected to Minecraft right now",
             Reaction::Warning => "Something went wrong",
         }
     }
@@ -7,26 +7,136 @@ use crate::{
     minecraft,
     payloads::{
         command::MinecraftCommand,
-        events::{ChatEvent, Message, RawChatEvent, Response},
+        events::{ChatEvent, Message, RawChatEvent, Response, Whisper},
+    },
+    sanitizer::{
+        length::{self, Length},
+        transliterate, CleanString, ValidIGN,
     },
-    sanitizer::CleanString,
 };
+use lazy_regex::regex_replace_all;
+use std::fmt::Display;
 use strum::EnumIs;
+use twilight_model::id::{marker::UserMarker, Id};
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct ChatCommand {
     pub author: CleanString,
     pub message: CleanString,
-    pub chat: Chat,
+    pub destination: Destination,
+}
+
+/// Where a [`ChatCommand`] is sent in Minecraft
+#[derive(Debug, Clone, PartialEq, EnumIs)]
+pub enum Destination {
+    Chat(Chat),
+    Whisper(ValidIGN),
+}
+
+impl Destination {
+    /// The command the message is sent with, without the leading `/`
+    fn prefix(&self) -> String {
+        match self {
+            Destination::Chat(chat) => chat.prefix().to_string(),
+            Destination::Whisper(player) => format!("msg {player}"),
+        }
+    }
+}
+
+/// The text of a Discord message, along with anything sent with it that can't be shown in game
+#[derive(Debug, Default)]
+pub struct Content {
+    pub text: String,
+    pub placeholders: Vec<Placeholder>,
+    /// Whether the message is being sent again after an edit, which is marked after it
+    pub edited: bool,
+}
+
+impl From<String> for Content {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            placeholders: Vec::new(),
+            edited: false,
+        }
+    }
+}
+
+impl Content {
+    /// The text with custom emojis written as `:name:`, followed by the placeholders
+    fn render(self) -> String {
+        let text = regex_replace_all!(r"<a?:(\w+):\d+>", &self.text, |_, name: &str| {
+            format!(":{name}:")
+        });
+
+        std::iter::once(text.trim().to_string())
+            .chain(self.placeholders.iter().map(ToString::to_string))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Something sent with a Discord message, shown in game as a short description
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placeholder {
+    Attachment {
+        filename: String,
+        content_type: Option<String>,
+    },
+    Sticker(String),
+    Embed(Option<String>),
+}
+
+impl Display for Placeholder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Placeholder::Attachment {
+                filename,
+                content_type,
+            } => {
+                let kind = content_type
+                    .as_deref()
+                    .and_then(|content_type| content_type.split('/').next())
+                    .filter(|kind| matches!(*kind, "image" | "video" | "audio"))
+                    .unwrap_or("file");
+
+                write!(f, "[{kind}: {filename}]")
+            }
+            Placeholder::Sticker(name) => write!(f, "[sticker: {name}]"),
+            Placeholder::Embed(Some(title)) => write!(f, "[embed: {title}]"),
+            Placeholder::Embed(None) => write!(f, "[embed]"),
+        }
+    }
 }
 
 impl ChatCommand {
     pub fn new(
         author: String,
-        message: String,
+        message: impl Into<Content>,
         chat: Chat,
     ) -> Result<(Self, Vec<Reaction>), Reaction> {
+        Self::build(author, message.into(), Destination::Chat(chat))
+    }
+
+    /// A message whispered to `player`
+    pub fn whisper(
+        author: String,
+        message: impl Into<Content>,
+        player: ValidIGN,
+    ) -> Result<(Self, Vec<Reaction>), Reaction> {
+        Self::build(author, message.into(), Destination::Whisper(player))
+    }
+
+    fn build(
+        author: String,
+        message: Content,
+        destination: Destination,
+    ) -> Result<(Self, Vec<Reaction>), Reaction> {
+        // The marker goes after the message, so a message which is too long can't cut it off
+        let marker = if message.edited { " (edited)" } else { "" };
+        let message = message.render();
         let clean_author = CleanString::from(author.clone());
         let clean_message = CleanString::from(message.clone());
 
@@ -36,24 +146,35 @@ impl ChatCommand {
 
         let mut issues = vec![];
 
-        let clean_trimmed_message = clean_message
-            .chars()
-            .take(256 - 1 - chat.prefix().chars().count() - 1 - clean_author.chars().count() - 2)
-            .collect::<CleanString>();
-
-        if author.as_str() != clean_author || message.as_str() != clean_message {
+        // Emojis and styled letters are rewritten rather than removed, so they aren't illegal characters
+        if transliterate(&author) != *clean_author || transliterate(&message) != *clean_message {
             issues.push(reactions::IllegalCharacters);
         }
 
-        if clean_message.chars().count() != clean_trimmed_message.chars().count() {
-            issues.push(reactions::TooLong);
+        // Everything sent around the message counts towards Minecraft's limit
+        let used = Length::of(&format!(
+            "/{prefix} {clean_author}: {marker}",
+            prefix = destination.prefix()
+        ));
+
+        let clean_trimmed_message = match length::fit(&clean_message, used) {
+            Ok(()) => clean_message,
+            Err(overflow) => {
+                issues.push(reactions::TooLong);
+                CleanString::from(clean_message[..overflow.fits].to_string())
+            }
+        };
+
+        // The author's name can be too long for any of the message to fit
+        if clean_trimmed_message.is_empty() {
+            return Err(reactions::TooLong);
         }
 
         Ok((
             Self {
                 author: clean_author,
-                message: clean_trimmed_message,
-                chat,
+                message: CleanString::from(format!("{clean_trimmed_message}{marker}")),
+                destination,
             },
             issues,
         ))
@@ -69,12 +190,15 @@ pub enum ChatCommandResponse {
 impl RunCommand for ChatCommand {
     type Response = ChatCommandResponse;
 
-    fn get_command(&self) -> Result<MinecraftCommand, ChatCommandResponse> {
-        Ok(MinecraftCommand::ChatMessage(
-            self.author.clone(),
-            self.message.clone(),
-            self.chat,
-        ))
+    fn get_command(&self, _user: Id<UserMarker>) -> Result<MinecraftCommand, ChatCommandResponse> {
+        Ok(match &self.destination {
+            Destination::Chat(chat) => {
+                MinecraftCommand::ChatMessage(self.author.clone(), self.message.clone(), *chat)
+            }
+            Destination::Whisper(player) => {
+                MinecraftCommand::Whisper(player.clone(), self.author.clone(), self.message.clone())
+            }
+        })
     }
 
     fn check_event(&self, event: RawChatEvent) -> Option<ChatCommandResponse> {
@@ -85,7 +209,7 @@ impl RunCommand for ChatCommand {
                 author,
                 content,
                 chat,
-            }) if self.chat == chat
+            }) if self.destination == Destination::Chat(chat)
                 && minecraft::USERNAME
                     .wait()
                     .read()
@@ -96,6 +220,17 @@ impl RunCommand for ChatCommand {
                 Some(Success)
             }
 
+            ChatEvent::Whisper(Whisper {
+                player,
+                content,
+                incoming: false,
+            }) if matches!(&self.destination, Destination::Whisper(to) if to.eq_ignore_ascii_case(player))
+                && content.starts_with(&*self.author)
+                && content.ends_with(&*self.message) =>
+            {
+                Some(Success)
+            }
+
             ChatEvent::CommandResponse(response) => match response {
                 Response::BotNotInGuild => Some(Failure(reactions::NotInGuild)),
                 Response::CommandDisabled => Some(Failure(reactions::Warning)),
@@ -108,11 +243,25 @@ impl RunCommand for ChatCommand {
                     return Some(Failure(reactions::Muted));
                 }
 
-                if message == "You don't have access to the officer chat!" && self.chat.is_officer()
+                if message == "You don't have access to the officer chat!"
+                    && self.destination == Destination::Chat(Chat::Officer)
                 {
                     return Some(Failure(reactions::NoPermission));
                 }
 
+                if message == "You are not in a party right now."
+                    && self.destination == Destination::Chat(Chat::Party)
+                {
+                    return Some(Failure(reactions::NotInParty));
+                }
+
+                if (message == "That player is not online!"
+                    || message.starts_with("Can't find a player by the name of"))
+                    && self.destination.is_whisper()
+                {
+                    return Some(Failure(reactions::PlayerOffline));
+                }
+
                 if message == "You must be in a guild to use this command!" {
                     return Some(Failure(reactions::NotInGuild));
                 }
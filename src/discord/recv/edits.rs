@@ -0,0 +1,255 @@
+//! Discord messages which can still be edited or deleted after being bridged
+
+use crate::config;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use twilight_model::id::{marker::MessageMarker, Id};
+
+static FOLLOWED: Lazy<Mutex<Followed>> = Lazy::new(|| Mutex::new(Followed::default()));
+
+/// Messages sent to Minecraft recently, or still waiting to be sent
+#[derive(Default)]
+struct Followed {
+    messages: HashMap<Id<MessageMarker>, Message>,
+}
+
+struct Message {
+    /// When the message was first bridged, so editing it again doesn't extend the window
+    received: Instant,
+    content: String,
+    /// Cancels the commands sent for the message, if they haven't reached Minecraft yet
+    cancel: Vec<oneshot::Sender<()>>,
+    /// Whether Minecraft has said a version of the message was sent
+    sent: bool,
+}
+
+/// How an edit is sent to Minecraft
+#[derive(Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// Nothing had been sent yet, so the edit is sent in place of the original
+    Replaced,
+    /// Players have already seen the original, so the edit is sent as a correction
+    Corrected,
+}
+
+impl Followed {
+    fn track(
+        &mut self,
+        id: Id<MessageMarker>,
+        content: String,
+        now: Instant,
+        window: Duration,
+    ) -> oneshot::Receiver<()> {
+        // Forget messages which can't be edited anymore, and have nothing left to cancel
+        self.messages.retain(|_, message| {
+            message.received + window > now || message.cancel.iter().any(|tx| !tx.is_closed())
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let message = self.messages.entry(id).or_insert_with(|| Message {
+            received: now,
+            content: String::new(),
+            cancel: Vec::new(),
+            sent: false,
+        });
+
+        message.content = content;
+        message.cancel.push(tx);
+
+        rx
+    }
+
+    /// How to send an edit which changed the content of a message within the window, if it should be sent at all.
+    /// Anything still waiting to be sent for the message is cancelled, so only the newest content is sent
+    fn edited(
+        &mut self,
+        id: Id<MessageMarker>,
+        content: &str,
+        now: Instant,
+        window: Duration,
+    ) -> Option<Edit> {
+        let message = self.messages.get_mut(&id)?;
+
+        if message.received + window <= now || message.content == content {
+            return None;
+        }
+
+        for tx in message.cancel.drain(..) {
+            // Commands which were already sent, or never will be, have stopped listening
+            tx.send(()).ok();
+        }
+
+        message.content = content.to_string();
+
+        Some(if message.sent {
+            Edit::Corrected
+        } else {
+            Edit::Replaced
+        })
+    }
+
+    fn sent(&mut self, id: Id<MessageMarker>) {
+        if let Some(message) = self.messages.get_mut(&id) {
+            message.sent = true;
+        }
+    }
+
+    fn delete(&mut self, id: Id<MessageMarker>) {
+        let Some(message) = self.messages.remove(&id) else {
+            return;
+        };
+
+        for tx in message.cancel {
+            // Commands which were already sent have stopped listening
+            tx.send(()).ok();
+        }
+    }
+}
+
+/// Follow a message being sent to Minecraft, returning a future which finishes if the message is deleted
+pub fn track(id: Id<MessageMarker>, content: String) -> impl Future<Output = ()> {
+    let rx = FOLLOWED
+        .lock()
+        .track(id, content, Instant::now(), config().discord.edit_window);
+
+    async move {
+        // The message was forgotten without being deleted, so there is nothing to cancel
+        if rx.await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// How an edit should be sent to Minecraft, or `None` if it shouldn't be
+pub fn edited(id: Id<MessageMarker>, content: &str) -> Option<Edit> {
+    FOLLOWED
+        .lock()
+        .edited(id, content, Instant::now(), config().discord.edit_window)
+}
+
+/// Remember that Minecraft sent a version of the message, so later edits are sent as corrections
+pub fn sent(id: Id<MessageMarker>) {
+    FOLLOWED.lock().sent(id);
+}
+
+/// Cancel anything still waiting to be sent for a deleted message
+pub fn delete(id: Id<MessageMarker>) {
+    FOLLOWED.lock().delete(id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test_case("Hello, world!", 30, Some(Edit::Corrected) ; "Edited")]
+    #[test_case("Hello, world!", 60, None ; "Too late")]
+    #[test_case("Hello, wrld!", 30, None ; "Unchanged")]
+    fn edits(content: &str, after: u64, expected: Option<Edit>) {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        drop(followed.track(Id::new(1), "Hello, wrld!".to_string(), now, WINDOW));
+        followed.sent(Id::new(1));
+
+        assert_eq!(
+            followed.edited(
+                Id::new(1),
+                content,
+                now + Duration::from_secs(after),
+                WINDOW
+            ),
+            expected
+        );
+        assert_eq!(followed.edited(Id::new(2), content, now, WINDOW), None);
+    }
+
+    #[test]
+    fn not_sent_yet() {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        let mut original = followed.track(Id::new(1), "one".to_string(), now, WINDOW);
+
+        assert_eq!(
+            followed.edited(Id::new(1), "two", now, WINDOW),
+            Some(Edit::Replaced)
+        );
+        assert_eq!(original.try_recv(), Ok(()));
+    }
+
+    #[test]
+    fn not_sent() {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        // Minecraft refused to send the original, so it stopped listening without being sent
+        drop(followed.track(Id::new(1), "one".to_string(), now, WINDOW));
+
+        assert_eq!(
+            followed.edited(Id::new(1), "two", now, WINDOW),
+            Some(Edit::Replaced)
+        );
+    }
+
+    #[test]
+    fn edited_twice() {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        drop(followed.track(Id::new(1), "one".to_string(), now, WINDOW));
+        followed.sent(Id::new(1));
+
+        let later = now + Duration::from_secs(40);
+        assert_eq!(
+            followed.edited(Id::new(1), "two", later, WINDOW),
+            Some(Edit::Corrected)
+        );
+        let _second = followed.track(Id::new(1), "two".to_string(), later, WINDOW);
+
+        // The window starts when the message is first sent
+        assert_eq!(
+            followed.edited(Id::new(1), "three", now + WINDOW, WINDOW),
+            None
+        );
+        // Once any version has been sent, later ones are corrections too
+        assert_eq!(
+            followed.edited(Id::new(1), "three", later, WINDOW),
+            Some(Edit::Corrected)
+        );
+    }
+
+    #[test]
+    fn deleted() {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        let mut rx = followed.track(Id::new(1), "Hello, world!".to_string(), now, WINDOW);
+        let mut other = followed.track(Id::new(2), "Hello, world!".to_string(), now, WINDOW);
+
+        followed.delete(Id::new(1));
+
+        assert_eq!(rx.try_recv(), Ok(()));
+        assert!(other.try_recv().is_err());
+        assert_eq!(
+            followed.edited(Id::new(1), "Goodbye, world!", now, WINDOW),
+            None
+        );
+    }
+
+    #[test]
+    fn forgotten() {
+        let now = Instant::now();
+        let mut followed = Followed::default();
+        drop(followed.track(Id::new(1), "sent".to_string(), now, WINDOW));
+        let _waiting = followed.track(Id::new(2), "queued".to_string(), now, WINDOW);
+
+        followed.track(Id::new(3), "new".to_string(), now + WINDOW, WINDOW);
+
+        assert!(!followed.messages.contains_key(&Id::new(1)));
+        assert!(followed.messages.contains_key(&Id::new(2)));
+    }
+}
@@ -1,9 +1,17 @@
-use super::reactions;
+use super::{chat_command::Placeholder, reactions};
+use crate::storage;
 use lazy_regex::regex_replace_all;
 use std::sync::Arc;
+use tokio::task::JoinHandle;
 use twilight_cache_inmemory::InMemoryCache;
 use twilight_http::{request::channel::reaction::RequestReactionType, Client as HttpClient};
-use twilight_model::channel::{message::Mention, Message};
+use twilight_model::{
+    channel::{message::Mention, Message},
+    id::{
+        marker::{ChannelMarker, MessageMarker},
+        Id,
+    },
+};
 
 pub trait MessageExt {
     /// Returns the display name of the author of the message
@@ -13,8 +21,21 @@ pub trait MessageExt {
     /// Returns the content of the message with user mentions replaced with their display names,
     /// channel mentions replaced with their names, and role mentions replaced with their names
     fn content_clean(&self, cache: &InMemoryCache) -> String;
+    /// Returns the attachments, stickers and embeds sent with the message, which can't be shown in game
+    fn placeholders(&self) -> Vec<Placeholder>;
+    /// Returns the player a bridged whisper came from, if the message is one the bridge sent
+    fn whisper_sender(&self, cache: &InMemoryCache) -> Option<&str>;
     /// Reacts to the message with the given reaction
     fn react(&self, http: Arc<HttpClient>, reaction: reactions::Reaction);
+    /// Reacts with [`reactions::Pending`] until [`MessageExt::resolve`] is called
+    fn react_pending(&self, http: Arc<HttpClient>) -> Option<PendingReaction>;
+    /// Replaces the pending reaction with the reaction for how sending the message went
+    fn resolve(
+        &self,
+        http: Arc<HttpClient>,
+        pending: Option<PendingReaction>,
+        reaction: reactions::Reaction,
+    );
 }
 
 impl MessageExt for Message {
@@ -82,23 +103,125 @@ impl MessageExt for Message {
         result
     }
 
+    fn placeholders(&self) -> Vec<Placeholder> {
+        let attachments = self
+            .attachments
+            .iter()
+            .map(|attachment| Placeholder::Attachment {
+                filename: attachment.filename.clone(),
+                content_type: attachment.content_type.clone(),
+            });
+
+        let stickers = self
+            .sticker_items
+            .iter()
+            .map(|sticker| Placeholder::Sticker(sticker.name.clone()));
+
+        // Links in the message are embedded by Discord, and are already in the text
+        let embeds = self
+            .embeds
+            .iter()
+            .filter(|embed| {
+                embed
+                    .url
+                    .as_ref()
+                    .is_none_or(|url| !self.content.contains(url.as_str()))
+            })
+            .map(|embed| Placeholder::Embed(embed.title.clone()));
+
+        attachments.chain(stickers).chain(embeds).collect()
+    }
+
+    fn whisper_sender(&self, cache: &InMemoryCache) -> Option<&str> {
+        // Whispers are sent through the channel's webhook named after the player, which anyone else's could imitate
+        if let Some(webhook) = self.webhook_id {
+            let ours = storage::read(|data| {
+                data.webhooks
+                    .get(&self.channel_id.to_string())
+                    .map(|stored| stored.id)
+            });
+
+            return (ours == Some(webhook.get())).then_some(self.author.name.as_str());
+        }
+
+        // In DMs they are sent by the bot as an embed
+        if cache.current_user().map(|user| user.id) != Some(self.author.id) {
+            return None;
+        }
+
+        self.embeds
+            .first()
+            .and_then(|embed| embed.author.as_ref())
+            .map(|author| author.name.as_str())
+    }
+
     fn react(&self, http: Arc<HttpClient>, reaction: reactions::Reaction) {
-        let channel_id = self.channel_id;
-        let id = self.id;
+        if let Some(emoji) = reaction.emoji() {
+            tokio::spawn(add_reaction(http, self.channel_id, self.id, emoji));
+        }
+    }
+
+    fn react_pending(&self, http: Arc<HttpClient>) -> Option<PendingReaction> {
+        let emoji = reactions::Pending.emoji()?;
+
+        Some(PendingReaction {
+            added: tokio::spawn(add_reaction(http, self.channel_id, self.id, emoji.clone())),
+            emoji,
+        })
+    }
+
+    fn resolve(
+        &self,
+        http: Arc<HttpClient>,
+        pending: Option<PendingReaction>,
+        reaction: reactions::Reaction,
+    ) {
+        let (channel_id, id) = (self.channel_id, self.id);
 
         tokio::spawn(async move {
-            if let Err(err) = http
-                .create_reaction(
-                    channel_id,
-                    id,
-                    &RequestReactionType::Unicode {
-                        name: reaction.emoji(),
-                    },
-                )
-                .await
-            {
-                tracing::warn!("Failed to react to message: {err}");
+            if let Some(PendingReaction { added, emoji }) = pending {
+                // The pending reaction can only be removed once it has been added
+                let _ = added.await;
+
+                if let Err(err) = http
+                    .delete_current_user_reaction(
+                        channel_id,
+                        id,
+                        &RequestReactionType::Unicode { name: &emoji },
+                    )
+                    .await
+                {
+                    tracing::warn!("Failed to remove reaction from message: {err}");
+                }
+            }
+
+            if let Some(emoji) = reaction.emoji() {
+                add_reaction(http, channel_id, id, emoji).await;
             }
         });
     }
 }
+
+/// A reaction shown until a message reaches Minecraft
+pub struct PendingReaction {
+    added: JoinHandle<()>,
+    emoji: String,
+}
+
+async fn add_reaction(
+    http: Arc<HttpClient>,
+    channel: Id<ChannelMarker>,
+    message: Id<MessageMarker>,
+    emoji: String,
+) {
+    if let Err(err) = http
+        .create_reaction(
+            channel,
+            message,
+            &RequestReactionType::Unicode { name: &emoji },
+        )
+        .await
+    {
+        tracing::warn!("Failed to react to message: {err}");
+    }
+}
@@ -1,16 +1,25 @@
 mod chat_command;
+mod edits;
 mod message_ext;
 
 use super::{
-    autocomplete,
-    commands::{Feedback, RunCommand},
-    reactions, Discord,
+    audit, autocomplete,
+    commands::{self, RunCommand},
+    feedback::{Feedback, Unsent},
+    links, pagination, reactions, Discord,
 };
-use crate::{bridge::Chat, config, discord::commands::SlashCommandResponse};
-use chat_command::{ChatCommand, ChatCommandResponse};
+use crate::{
+    bridge::Chat,
+    config,
+    discord::commands::SlashCommandResponse,
+    minecraft,
+    payloads::command::NotSent,
+    sanitizer::ValidIGN,
+    storage::{self, history, Counter},
+};
+use chat_command::{ChatCommand, ChatCommandResponse, Content, Destination};
 use message_ext::MessageExt;
 use std::{ops::Deref, sync::Arc};
-use tokio::sync::Mutex;
 use twilight_gateway::Event;
 use twilight_model::{
     application::{
@@ -20,7 +29,8 @@ use twilight_model::{
             InteractionData, InteractionType,
         },
     },
-    gateway::payload::incoming::{InteractionCreate, MessageCreate},
+    channel::Message,
+    gateway::payload::incoming::{InteractionCreate, MessageUpdate},
     http::interaction::{InteractionResponse, InteractionResponseType},
 };
 use twilight_util::builder::{embed::EmbedBuilder, InteractionResponseDataBuilder};
@@ -28,7 +38,7 @@ use twilight_webhook::cache::PermissionsSource;
 
 pub struct DiscordHandler {
     discord: Arc<Discord>,
-    pub feedback: Arc<Mutex<Feedback>>,
+    pub feedback: Feedback,
 }
 
 impl Deref for DiscordHandler {
@@ -42,10 +52,7 @@ impl Deref for DiscordHandler {
 impl DiscordHandler {
     pub fn new(discord: Arc<Discord>) -> Self {
         Self {
-            feedback: Arc::new(Mutex::new(Feedback {
-                tx: discord.sender.clone(),
-                rx: discord.receiver.new_receiver().deactivate(),
-            })),
+            feedback: Feedback::new(discord.sender.clone(), discord.receiver.new_receiver()),
             discord,
         }
     }
@@ -73,7 +80,18 @@ impl DiscordHandler {
                 tracing::info!("{} is connected!", ready.user.name);
             }
             Event::MessageCreate(message) => {
-                self.handle_message_create(*message).await;
+                self.handle_message(message.0, false).await;
+            }
+            Event::MessageUpdate(update) => {
+                if let Err(err) = self.handle_message_update(*update).await {
+                    tracing::error!("Failed to handle message edit: {err}")
+                }
+            }
+            Event::MessageDelete(message) => {
+                edits::delete(message.id);
+            }
+            Event::MessageDeleteBulk(messages) => {
+                messages.ids.into_iter().for_each(edits::delete);
             }
             Event::InteractionCreate(interaction) => {
                 self.handle_interaction_create(*interaction).await;
@@ -82,7 +100,29 @@ impl DiscordHandler {
         }
     }
 
-    async fn handle_message_create(&self, message: MessageCreate) {
+    /// Send an edited message to Minecraft again, if it was only just bridged
+    async fn handle_message_update(&self, update: MessageUpdate) -> anyhow::Result<()> {
+        let Some(content) = update.content else {
+            return Ok(());
+        };
+
+        let Some(edit) = edits::edited(update.id, &content) else {
+            return Ok(());
+        };
+
+        let message = self
+            .http
+            .message(update.channel_id, update.id)
+            .await?
+            .model()
+            .await?;
+
+        self.handle_message(message, edit == edits::Edit::Corrected)
+            .await;
+        Ok(())
+    }
+
+    async fn handle_message(&self, message: Message, edited: bool) {
         if message.author.bot {
             return;
         }
@@ -97,21 +137,48 @@ impl DiscordHandler {
         let author = if let Some(reply) = &message.referenced_message {
             format!(
                 "{author} ≫ {replying_to}",
-                author = message.get_author_display_name(),
-                replying_to = reply.get_author_display_name()
+                author = author_name(&message),
+                replying_to = author_name(reply)
             )
         } else {
-            message.get_author_display_name().to_string()
+            author_name(&message)
+        };
+        let content = Content {
+            text: message.content_clean(&self.cache).to_string(),
+            placeholders: message.placeholders(),
+            edited,
         };
-        let content = message.content_clean(&self.cache).to_string();
 
-        let chat = match message.channel_id.get() {
-            id if id == config().channels.guild => Chat::Guild,
-            id if id == config().channels.officer => Chat::Officer,
-            _ => return,
+        let is_whisper = if message.guild_id.is_some() {
+            Some(message.channel_id.get()) == config().channels.whispers
+        } else {
+            config().routes.whisper_dms
         };
 
-        let (command, issues) = match ChatCommand::new(author, content, chat) {
+        let command = if is_whisper {
+            // Replying to a whisper whispers back to whoever sent it
+            let Some(player) = message
+                .referenced_message
+                .as_deref()
+                .and_then(|reply| reply.whisper_sender(&self.cache))
+                .and_then(|sender| ValidIGN::try_from(sender).ok())
+            else {
+                return message.react(self.http.clone(), reactions::NoRecipient);
+            };
+
+            // Whispers are only sent by DM to the player's linked account, which may have been unlinked since
+            if message.guild_id.is_none() && links::user(&player) != Some(message.author.id) {
+                return message.react(self.http.clone(), reactions::NoRecipient);
+            }
+
+            ChatCommand::whisper(author_name(&message), content, player)
+        } else if let Some(chat) = Chat::from_channel(message.channel_id.get()) {
+            ChatCommand::new(author, content, chat)
+        } else {
+            return;
+        };
+
+        let (command, issues) = match command {
             Ok((command, issues)) => (command, issues),
             Err(issue) => {
                 return message.react(self.http.clone(), issue);
@@ -122,24 +189,58 @@ impl DiscordHandler {
             message.react(self.http.clone(), issue)
         }
 
-        match self
+        if !minecraft::is_connected() {
+            return message.react(self.http.clone(), reactions::Disconnected);
+        }
+
+        // Mutes are remembered between restarts, so there's no need to wait for the command to time out
+        if command.destination == Destination::Chat(Chat::Guild)
+            && minecraft::USERNAME
+                .get()
+                .is_some_and(|username| storage::is_muted(&username.read()))
+        {
+            return message.react(self.http.clone(), reactions::Muted);
+        }
+
+        // Whispers are private, so only chat messages are kept in the history
+        let history = match &command.destination {
+            Destination::Chat(chat) => Some((*chat, command.message.to_string())),
+            Destination::Whisper(_) => None,
+        };
+
+        let pending = message.react_pending(self.http.clone());
+        let id = message.id;
+
+        let reaction = match self
             .feedback
-            .lock()
-            .await
-            .execute(
+            .execute_cancellable(
                 command
-                    .get_command()
+                    .get_command(message.author.id)
                     .expect("ChatCommand.get_command() should always return Ok(_)"),
-                |event| command.check_event(event),
+                move |event| command.check_event(event),
+                edits::track(message.id, message.content.clone()),
+                move || edits::sent(id),
             )
             .await
         {
-            Some(ChatCommandResponse::Success) => {}
-            Some(ChatCommandResponse::Failure(reaction)) => {
-                message.react(self.http.clone(), reaction)
+            // The message was deleted or edited before it was sent, so there's nothing left to react to
+            Err(Unsent::Cancelled) => return,
+            Err(Unsent::NotSent(NotSent::TooLong)) => reactions::TooLong,
+            Err(Unsent::NotSent(NotSent::Dropped)) => reactions::Disconnected,
+            Ok(Some(ChatCommandResponse::Success)) => {
+                storage::increment(Counter::FromDiscord);
+
+                if let Some((chat, content)) = history {
+                    history::record(chat, true, &author_name(&message), &content);
+                }
+
+                reactions::Delivered
             }
-            None => message.react(self.http.clone(), reactions::TimedOut),
+            Ok(Some(ChatCommandResponse::Failure(reaction))) => reaction,
+            Ok(None) => reactions::TimedOut,
         };
+
+        message.resolve(self.http.clone(), pending, reaction);
     }
 
     async fn handle_interaction_create(&self, mut interaction: InteractionCreate) {
@@ -173,6 +274,22 @@ impl DiscordHandler {
                     tracing::error!("Failed to handle autocomplete interaction: {err}")
                 }
             }
+            InteractionType::MessageComponent => {
+                let InteractionData::MessageComponent(data) = interaction
+                    .data
+                    .take()
+                    .expect("MessageComponent interaction had no data")
+                else {
+                    panic!("InteractionType::MessageComponent should have InteractionData::MessageComponent as data")
+                };
+
+                if let Err(err) = self
+                    .handle_component_interaction(interaction, &data.custom_id)
+                    .await
+                {
+                    tracing::error!("Failed to handle component interaction: {err}")
+                }
+            }
             _ => {}
         }
     }
@@ -190,7 +307,7 @@ impl DiscordHandler {
 
             let embed = EmbedBuilder::new()
                 .description("Command not found")
-                .color(crate::discord::colours::RED)
+                .color(crate::discord::colours::red())
                 .build();
 
             return client
@@ -224,23 +341,111 @@ impl DiscordHandler {
             )
             .await?;
 
-        let embed = match command.get_command() {
-            Ok(minecraft_command) => self
-                .feedback
-                .lock()
-                .await
-                .execute(minecraft_command, |event| command.check_event(event))
-                .await
-                .unwrap_or_else(|| SlashCommandResponse::Timeout)
-                .into(),
+        // Moderation commands are logged along with their outcome, once it's known
+        let mut audited = None;
+
+        let response = if command.owner_only() && !commands::is_owner(interaction.author_id()) {
+            SlashCommandResponse::Failure(
+                "Only the owner of the bot can use this command".to_string(),
+            )
+        } else {
+            let user = interaction
+                .author_id()
+                .expect("Command interaction had no author");
+
+            let command = Arc::<dyn RunCommand<Response = SlashCommandResponse>>::from(command);
+            // Some commands answer straight away from files, such as searching the history
+            let minecraft_command = {
+                let command = command.clone();
+
+                tokio::task::spawn_blocking(move || command.get_command(user))
+                    .await
+                    .expect("Getting the command panicked")
+            };
+            if let Ok(sent) = &minecraft_command {
+                audited = audit::is_audited(sent).then(|| (user, sent.to_string()));
+            }
 
-            Err(response) => response.into(),
+            match minecraft_command {
+                Ok(_) if !minecraft::is_connected() => {
+                    SlashCommandResponse::Failure(reactions::Disconnected.description().to_string())
+                }
+
+                Ok(minecraft_command) if command.is_streaming() => {
+                    let collector = command.clone();
+
+                    self.feedback
+                        .execute_streaming(minecraft_command, move |event| {
+                            collector.collect_event(event)
+                        })
+                        .await
+                        .map(|events| command.finish(events))
+                        .unwrap_or_else(|| SlashCommandResponse::Timeout)
+                }
+
+                Ok(minecraft_command) => self
+                    .feedback
+                    .execute(minecraft_command, move |event| command.check_event(event))
+                    .await
+                    .unwrap_or_else(|| SlashCommandResponse::Timeout),
+
+                Err(response) => response,
+            }
         };
 
-        client
+        let (embed, components) = match response {
+            SlashCommandResponse::Pages(pages) => pagination::start(interaction.id, pages),
+            response => (response.into(), Vec::new()),
+        };
+
+        let entry = audited.map(|(user, sent)| audit::command_entry(user, &sent, &embed));
+
+        let result = client
             .update_response(&interaction.token)
             .embeds(Some(&[embed]))
             .expect("Invalid embeds in response")
+            .components(Some(&components))
+            .expect("Invalid components in response")
+            .await
+            .map(|_| ());
+
+        // The audit channel can wait until whoever used the command has their answer
+        if let Some(entry) = entry {
+            audit::log(&self.http, entry).await;
+        }
+
+        result
+    }
+
+    async fn handle_component_interaction(
+        &self,
+        interaction: InteractionCreate,
+        custom_id: &str,
+    ) -> Result<(), twilight_http::Error> {
+        // Other components may belong to another bot or a newer version
+        if !pagination::is_page_button(custom_id) {
+            return Ok(());
+        }
+
+        let client = self.http.interaction(interaction.application_id);
+
+        // Responses that are no longer stored lose their buttons
+        let data = match pagination::turn(custom_id) {
+            Some((embed, components)) => InteractionResponseDataBuilder::new()
+                .embeds([embed])
+                .components(components),
+            None => InteractionResponseDataBuilder::new().components([]),
+        };
+
+        client
+            .create_response(
+                interaction.id,
+                &interaction.token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::UpdateMessage,
+                    data: Some(data.build()),
+                },
+            )
             .await
             .map(|_| ())
     }
@@ -295,14 +500,25 @@ impl DiscordHandler {
     }
 }
 
+/// The author's linked IGN, or their display name if they haven't linked their account
+fn author_name(message: &Message) -> String {
+    links::ign(message.author.id)
+        .map(|ign| ign.to_string())
+        .unwrap_or_else(|| message.get_author_display_name().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::commands::testing::test_command;
-    use super::{reactions::Reaction, *};
+    use super::{chat_command::Placeholder, reactions::Reaction, *};
+    use crate::sanitizer::length::{self, Length};
     use test_case::test_case;
+    use twilight_model::id::Id;
 
     #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap(), "Guild > neytwoa: neyoa: Hello, world!" ; "guild")]
     #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Officer).unwrap(), "Officer > neytwoa: neyoa: Hello, world!" ; "officer")]
+    #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Party).unwrap(), "Party > [MVP+] neytwoa: neyoa: Hello, world!" ; "party")]
+    #[test_case(ChatCommand::whisper("neyoa".to_string(), "Hello, world!".to_string(), "Alpha".try_into().unwrap()).unwrap(), "To [VIP] alpha: neyoa: Hello, world!" ; "whisper")]
     fn success(command: (ChatCommand, Vec<Reaction>), message: &'static str) {
         assert!(command.1.is_empty());
         assert!(test_command(command.0, message).is_success());
@@ -314,6 +530,8 @@ mod tests {
     #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap().0, "You're currently guild muted for 59s!", Reaction::Muted ; "Muted (seconds)")]
     #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Officer).unwrap().0, "You don't have access to the officer chat!", Reaction::NoPermission ; "No permission")]
     #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap().0, "You must be in a guild to use this command!", Reaction::NotInGuild ; "Not in a guild")]
+    #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, world!".to_string(), Chat::Party).unwrap().0, "You are not in a party right now.", Reaction::NotInParty ; "Not in a party")]
+    #[test_case(ChatCommand::whisper("neyoa".to_string(), "Hello, world!".to_string(), "alpha".try_into().unwrap()).unwrap().0, "That player is not online!", Reaction::PlayerOffline ; "Player offline")]
     fn failures(command: ChatCommand, message: &'static str, reaction: Reaction) {
         let ChatCommandResponse::Failure(got) = test_command(command, message) else {
             panic!("Expected failure")
@@ -322,22 +540,75 @@ mod tests {
         assert_eq!(got, reaction);
     }
 
-    #[test_case(ChatCommand::new("😀".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap_err(), Reaction::EmptyField ; "Author")]
-    #[test_case(ChatCommand::new("neyoa".to_string(), "😀".to_string(), Chat::Guild).unwrap_err(), Reaction::EmptyField ; "Content")]
+    #[test_case(ChatCommand::new("你".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap_err(), Reaction::EmptyField ; "Author")]
+    #[test_case(ChatCommand::new("neyoa".to_string(), "你".to_string(), Chat::Guild).unwrap_err(), Reaction::EmptyField ; "Content")]
     fn empty_field(err: Reaction, reaction: Reaction) {
         assert_eq!(err, reaction);
     }
 
-    #[test_case(ChatCommand::new("ney😀oa".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap(), "Guild > neytwoa: neyoa: Hello, world!" ; "Author")]
-    #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, 😀world!".to_string(), Chat::Guild).unwrap(), "Guild > neytwoa: neyoa: Hello, world!" ; "Content")]
+    #[test_case(ChatCommand::new("ney你oa".to_string(), "Hello, world!".to_string(), Chat::Guild).unwrap(), "Guild > neytwoa: neyoa: Hello, world!" ; "Author")]
+    #[test_case(ChatCommand::new("neyoa".to_string(), "Hello, 你world!".to_string(), Chat::Guild).unwrap(), "Guild > neytwoa: neyoa: Hello, world!" ; "Content")]
     fn trimmed_content(command: (ChatCommand, Vec<Reaction>), message: &'static str) {
         assert_eq!(command.1, vec![Reaction::IllegalCharacters]);
         assert!(test_command(command.0, message).is_success());
     }
 
+    #[test_case("😀".to_string(), ":grinning:" ; "Emoji")]
+    #[test_case("gg 👋🏽 ｇｇ".to_string(), "gg :wave: gg" ; "Skin tone and full width")]
+    fn transliterated(content: String, expected: &str) {
+        let (command, issues) =
+            ChatCommand::new("neyoa".to_string(), content, Chat::Guild).unwrap();
+
+        assert!(issues.is_empty());
+        assert_eq!(expected, command.message);
+    }
+
+    #[test_case(Content { text: String::new(), placeholders: vec![Placeholder::Attachment { filename: "cat.png".to_string(), content_type: Some("image/png".to_string()) }], edited: false }, "[image: cat.png]" ; "Image")]
+    #[test_case(Content { text: "look".to_string(), placeholders: vec![Placeholder::Attachment { filename: "notes.txt".to_string(), content_type: None }, Placeholder::Sticker("Wave".to_string())], edited: false }, "look [file: notes.txt] [sticker: Wave]" ; "File and sticker")]
+    #[test_case(Content { text: "gg".to_string(), placeholders: vec![Placeholder::Embed(Some("Skyblock".to_string())), Placeholder::Embed(None)], edited: false }, "gg [embed: Skyblock] [embed]" ; "Embeds")]
+    #[test_case(Content::from("nice <:pog:123456789> <a:dance:987654321>".to_string()), "nice :pog: :dance:" ; "Custom emojis")]
+    fn placeholders(content: Content, expected: &str) {
+        let (command, issues) =
+            ChatCommand::new("neyoa".to_string(), content, Chat::Guild).unwrap();
+
+        assert!(issues.is_empty());
+        assert_eq!(expected, command.message);
+    }
+
     #[test_case(ChatCommand::new("a".repeat(256 - 6 - 13 + 1), "Hello, world!".to_string(), Chat::Guild).unwrap() ; "Author")]
     #[test_case(ChatCommand::new("neyoa".to_string(), "a".repeat(256 - 6 - 5 + 1), Chat::Guild).unwrap() ; "Content")]
+    #[test_case(ChatCommand::new("neyoa".to_string(), "я".repeat(256 - 6 - 5 + 1), Chat::Guild).unwrap() ; "Counted as Minecraft counts")]
     fn too_long(command: (ChatCommand, Vec<Reaction>)) {
         assert_eq!(command.1, vec![Reaction::TooLong]);
+
+        let sent = command.0.get_command(Id::new(1)).unwrap().to_string();
+        assert_eq!(length::fit(&sent, Length::default()), Ok(()));
+    }
+
+    #[test]
+    fn multibyte_fits() {
+        // Twice as many bytes as characters, but Minecraft only limits the bytes to three times the characters
+        let (command, issues) =
+            ChatCommand::new("neyoa".to_string(), "я".repeat(256 - 6 - 5), Chat::Guild).unwrap();
+
+        assert!(issues.is_empty());
+        assert_eq!(command.message.chars().count(), 256 - 6 - 5);
+    }
+
+    #[test]
+    fn edited_too_long() {
+        let content = Content {
+            text: "a".repeat(300),
+            placeholders: Vec::new(),
+            edited: true,
+        };
+        let (command, issues) =
+            ChatCommand::new("neyoa".to_string(), content, Chat::Guild).unwrap();
+
+        assert_eq!(issues, vec![Reaction::TooLong]);
+        assert!(command.message.ends_with(" (edited)"));
+
+        let sent = command.get_command(Id::new(1)).unwrap().to_string();
+        assert_eq!(length::fit(&sent, Length::default()), Ok(()));
     }
 }
@@ -0,0 +1,438 @@
+use super::{colours, links, status};
+use crate::{config, payloads::events::GuildRoster};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Notify;
+use twilight_cache_inmemory::InMemoryCache;
+use twilight_model::{
+    channel::message::Embed,
+    id::{
+        marker::{GuildMarker, RoleMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+/// The gateway cache, for anything which needs Discord's state outside of an event
+pub(super) static CACHE: OnceCell<Arc<InMemoryCache>> = OnceCell::new();
+static RANKS: Lazy<Mutex<Ranks>> = Lazy::new(|| Mutex::new(Ranks::default()));
+/// Woken whenever a rank changes, so the roles can be synced
+static SYNC: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// The guild rank of every member the bridge knows about
+#[derive(Default)]
+struct Ranks {
+    /// Lowercase IGNs, with `None` for players known to have left the guild
+    members: HashMap<String, Option<String>>,
+    /// Whether every guild member is known, so anyone missing isn't in the guild
+    complete: bool,
+}
+
+/// A Discord member whose roles may need to change
+pub struct Member {
+    pub user: Id<UserMarker>,
+    /// The IGN the member linked with `/link`, the only way they can be given a role
+    pub linked: Option<String>,
+    /// Their nickname and username, which are matched against IGNs to suggest linking if they haven't
+    pub names: Vec<String>,
+    pub roles: Vec<Id<RoleMarker>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub user: Id<UserMarker>,
+    pub ign: Option<String>,
+    pub role: Id<RoleMarker>,
+    /// Whether the role is added, rather than removed
+    pub add: bool,
+}
+
+/// A member who hasn't linked, but whose name matches a guild member's IGN
+#[derive(Debug, PartialEq)]
+pub struct Suggestion {
+    pub user: Id<UserMarker>,
+    pub ign: String,
+}
+
+/// Everything role sync would do, and the links which would let it do more
+pub struct Plan {
+    pub changes: Vec<Change>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Replace every rank with a complete list of guild members
+pub fn replace_ranks(roster: &GuildRoster) {
+    let mut ranks = RANKS.lock();
+
+    ranks.members = roster
+        .ranks
+        .iter()
+        .flat_map(|section| {
+            section
+                .members
+                .iter()
+                .map(|member| (member.to_lowercase(), Some(section.rank.to_string())))
+        })
+        .collect();
+    ranks.complete = true;
+
+    SYNC.notify_one();
+}
+
+/// Set a member's rank, or `None` if they've left the guild
+pub fn set_rank(ign: &str, rank: Option<&str>) {
+    RANKS
+        .lock()
+        .members
+        .insert(ign.to_lowercase(), rank.map(str::to_string));
+
+    SYNC.notify_one();
+}
+
+/// Work out which roles need to be added and removed for every member's roles to match their rank
+pub fn plan() -> Plan {
+    let Some(members) = guild().and_then(discord_members) else {
+        return Plan {
+            changes: Vec::new(),
+            suggestions: Vec::new(),
+        };
+    };
+    let ranks = RANKS.lock();
+
+    Plan {
+        changes: changes(
+            &ranks,
+            &config()
+                .roles
+                .ranks
+                .iter()
+                .map(|(rank, role)| (rank.to_lowercase(), Id::new(*role)))
+                .collect(),
+            &members,
+        ),
+        suggestions: suggestions(&ranks, &members),
+    }
+}
+
+fn changes(
+    ranks: &Ranks,
+    roles: &HashMap<String, Id<RoleMarker>>,
+    members: &[Member],
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for member in members {
+        // Anyone can take a name, so only a verified link gets a role
+        let ign = member.linked.clone();
+
+        let role = match ign
+            .as_ref()
+            .and_then(|ign| ranks.members.get(&ign.to_lowercase()))
+        {
+            Some(Some(rank)) => roles.get(&rank.to_lowercase()).copied(),
+            Some(None) => None,
+            // Only take roles away from unknown players once every guild member is known
+            None if ranks.complete => None,
+            None => continue,
+        };
+
+        if let Some(role) = role.filter(|role| !member.roles.contains(role)) {
+            changes.push(Change {
+                user: member.user,
+                ign: ign.clone(),
+                role,
+                add: true,
+            });
+        }
+
+        for managed in roles.values() {
+            if Some(*managed) != role && member.roles.contains(managed) {
+                changes.push(Change {
+                    user: member.user,
+                    ign: ign.clone(),
+                    role: *managed,
+                    add: false,
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Unlinked members whose nickname or username is the IGN of someone in the guild
+fn suggestions(ranks: &Ranks, members: &[Member]) -> Vec<Suggestion> {
+    members
+        .iter()
+        .filter(|member| member.linked.is_none())
+        .filter_map(|member| {
+            let ign = member
+                .names
+                .iter()
+                .find(|name| matches!(ranks.members.get(&name.to_lowercase()), Some(Some(_))))?;
+
+            Some(Suggestion {
+                user: member.user,
+                ign: ign.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The Discord server the guild channel is in
+fn guild() -> Option<Id<GuildMarker>> {
+    CACHE
+        .get()?
+        .channel(Id::new(config().channels.guild))?
+        .guild_id
+}
+
+fn discord_members(guild: Id<GuildMarker>) -> Option<Vec<Member>> {
+    let cache = CACHE.get()?;
+    let users = cache.guild_members(guild)?;
+
+    Some(
+        users
+            .iter()
+            .filter_map(|user| {
+                let member = cache.member(guild, *user)?;
+                let mut names = vec![cache.user(*user)?.name.clone()];
+                names.extend(member.nick().map(str::to_string));
+
+                Some(Member {
+                    user: *user,
+                    linked: links::ign(*user).map(|ign| ign.to_string()),
+                    names,
+                    roles: member.roles().to_vec(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Sync roles whenever a rank changes, if syncing is turned on
+pub async fn run() {
+    loop {
+        SYNC.notified().await;
+
+        if !config().roles.sync {
+            continue;
+        }
+
+        let (Some(http), Some(guild)) = (status::HTTP.get(), guild()) else {
+            continue;
+        };
+
+        for Change {
+            user, role, add, ..
+        } in plan().changes
+        {
+            let result = if add {
+                http.add_guild_member_role(guild, user, role).await
+            } else {
+                http.remove_guild_member_role(guild, user, role).await
+            };
+
+            if let Err(err) = result {
+                tracing::error!("Failed to update roles of {user}: {err}");
+            }
+        }
+    }
+}
+
+/// Describe the changes, for checking the rank to role mapping before turning syncing on
+pub fn report(
+    Plan {
+        changes,
+        suggestions,
+    }: &Plan,
+) -> Embed {
+    let line = |change: &Change| {
+        format!(
+            "<@{user}>{ign} <@&{role}>",
+            user = change.user,
+            ign = change
+                .ign
+                .as_ref()
+                .map(|ign| format!(" (`{ign}`)"))
+                .unwrap_or_default(),
+            role = change.role
+        )
+    };
+    let field = |name: &str, add: bool| {
+        let lines = changes
+            .iter()
+            .filter(|change| change.add == add)
+            .map(line)
+            .collect::<Vec<_>>();
+
+        (!lines.is_empty()).then(|| EmbedFieldBuilder::new(name, truncate_lines(&lines)))
+    };
+
+    let mut embed = EmbedBuilder::new()
+        .title("Role Sync")
+        .footer(EmbedFooterBuilder::new(if config().roles.sync {
+            "These changes are being made"
+        } else {
+            "Dry run, turn on roles.sync to make these changes"
+        }))
+        .color(colours::green());
+
+    if changes.is_empty() {
+        embed = embed.description("Everyone's roles already match their rank");
+    }
+
+    let suggested = suggestions
+        .iter()
+        .map(|Suggestion { user, ign }| format!("<@{user}> (`{ign}`)"))
+        .collect::<Vec<_>>();
+    let suggested = (!suggested.is_empty()).then(|| {
+        EmbedFieldBuilder::new(
+            "Not linked, but named like a member (they can use /link)",
+            truncate_lines(&suggested),
+        )
+    });
+
+    for field in [field("Add", true), field("Remove", false), suggested]
+        .into_iter()
+        .flatten()
+    {
+        embed = embed.field(field);
+    }
+
+    embed.build()
+}
+
+/// Join the lines, leaving out as many as needed to fit in an embed field
+fn truncate_lines(lines: &[String]) -> String {
+    const MAX_LENGTH: usize = 1024;
+
+    let mut value = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let remaining = format!("\nand {count} more", count = lines.len() - index);
+
+        if value.len() + line.len() + 1 + remaining.len() > MAX_LENGTH {
+            value.push_str(&remaining);
+            break;
+        }
+
+        if index > 0 {
+            value.push('\n');
+        }
+        value.push_str(line);
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMBER: Id<RoleMarker> = Id::new(10);
+    const OFFICER: Id<RoleMarker> = Id::new(11);
+
+    fn roles() -> HashMap<String, Id<RoleMarker>> {
+        HashMap::from([
+            ("member".to_string(), MEMBER),
+            ("officer".to_string(), OFFICER),
+        ])
+    }
+
+    fn ranks(complete: bool) -> Ranks {
+        Ranks {
+            members: HashMap::from([
+                ("neyoa".to_string(), Some("Officer".to_string())),
+                ("neytwoa".to_string(), Some("Member".to_string())),
+                ("alpha".to_string(), None),
+            ]),
+            complete,
+        }
+    }
+
+    fn member(user: u64, linked: Option<&str>, name: &str, roles: &[Id<RoleMarker>]) -> Member {
+        Member {
+            user: Id::new(user),
+            linked: linked.map(str::to_string),
+            names: vec![name.to_string()],
+            roles: roles.to_vec(),
+        }
+    }
+
+    #[test]
+    fn promoted() {
+        let changes = changes(
+            &ranks(false),
+            &roles(),
+            &[member(1, Some("Neyoa"), "someone", &[MEMBER])],
+        );
+
+        assert_eq!(
+            changes,
+            [
+                Change {
+                    user: Id::new(1),
+                    ign: Some("Neyoa".to_string()),
+                    role: OFFICER,
+                    add: true
+                },
+                Change {
+                    user: Id::new(1),
+                    ign: Some("Neyoa".to_string()),
+                    role: MEMBER,
+                    add: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn matched_by_name() {
+        let members = [
+            member(2, None, "NeyTwoa", &[]),
+            member(3, None, "alpha", &[]),
+            member(1, Some("neyoa"), "neyoa", &[OFFICER]),
+        ];
+
+        assert!(changes(&ranks(false), &roles(), &members).is_empty());
+        assert_eq!(
+            suggestions(&ranks(false), &members),
+            [Suggestion {
+                user: Id::new(2),
+                ign: "NeyTwoa".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn left() {
+        let changes = changes(
+            &ranks(false),
+            &roles(),
+            &[member(3, Some("alpha"), "someone", &[MEMBER])],
+        );
+
+        assert_eq!(changes.len(), 1);
+        assert!(!changes[0].add);
+    }
+
+    #[test]
+    fn unknown() {
+        let members = [member(4, None, "bravo", &[OFFICER])];
+
+        assert!(changes(&ranks(false), &roles(), &members).is_empty());
+        assert_eq!(changes(&ranks(true), &roles(), &members).len(), 1);
+    }
+
+    #[test]
+    fn up_to_date() {
+        let members = [
+            member(1, Some("neyoa"), "neyoa", &[OFFICER]),
+            member(5, None, "charlie", &[]),
+        ];
+
+        assert!(changes(&ranks(true), &roles(), &members).is_empty());
+    }
+}
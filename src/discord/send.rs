@@ -1,17 +1,19 @@
 use super::avatar_url;
 use crate::{
     bridge::Chat,
-    discord::Discord,
+    config::{self, Route},
+    discord::{audit, links, mentions, roles, Discord},
     minecraft,
-    payloads::events::{self, ChatEvent, Message, RawChatEvent, Toggle},
+    payloads::events::{self, ChatEvent, Message, RawChatEvent, Toggle, Whisper},
+    storage::{self, history, Counter, StoredWebhook},
 };
 use std::{ops::Deref, sync::Arc};
 use twilight_model::{
-    channel::{
-        message::{AllowedMentions, Embed, MentionType},
-        Webhook,
+    channel::message::{AllowedMentions, Embed},
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
     },
-    id::{marker::ChannelMarker, Id},
 };
 use twilight_util::builder::embed::{EmbedAuthorBuilder, EmbedBuilder, ImageSource};
 
@@ -32,8 +34,23 @@ impl MinecraftHandler {
 
     pub async fn handle_event(&self, event: RawChatEvent) {
         self.add_event_to_autocomplete(event.as_chat_event());
+        self.verify_link(event.as_chat_event()).await;
+        self.update_ranks(event.as_chat_event());
+        track_mutes(event.as_chat_event());
 
-        match event.as_chat_event() {
+        if let Some(entry) = audit::event_entry(&event.as_chat_event()) {
+            audit::log(&self.http, entry).await;
+        }
+
+        let event = event.as_chat_event();
+        let Some(route) = route(&event) else {
+            return;
+        };
+
+        let config = config();
+        let channels = config.routes.channels(route).iter().map(|id| Id::new(*id));
+
+        match event {
             ChatEvent::Message(Message {
                 author,
                 content,
@@ -43,65 +60,69 @@ impl MinecraftHandler {
                     return; // Don't send our own messages to guild chat
                 }
 
-                let webhook = self.get_webhook(chat).await;
+                storage::increment(Counter::FromMinecraft);
+                history::record(chat, false, author, content);
 
-                if let Err(err) = self
-                    .http
-                    .execute_webhook(
-                        webhook.id,
-                        webhook.token.as_ref().expect("Webhook has no token"),
+                for channel in channels {
+                    // Members can only be looked up in the server the channel is in
+                    let (content, mentioned) = match self
+                        .cache
+                        .channel(channel)
+                        .and_then(|channel| channel.guild_id)
+                    {
+                        Some(guild) => mentions::replace(content, |name| {
+                            mentions::find_member(&self.cache, guild, name)
+                        }),
+                        None => (content.to_string(), Vec::new()),
+                    };
+
+                    self.send_as(channel, author, Some(&content), &[], &mentioned)
+                        .await;
+                }
+            }
+
+            ChatEvent::Whisper(Whisper {
+                player, content, ..
+            }) => {
+                for channel in channels {
+                    self.send_as(channel, player, Some(content), &[], &[]).await;
+                }
+
+                let embed = EmbedBuilder::new()
+                    .author(
+                        EmbedAuthorBuilder::new(player)
+                            .icon_url(ImageSource::url(avatar_url(player)).expect("Invalid URL"))
+                            .build(),
                     )
-                    .username(author)
-                    .expect("Invalid webhook username")
-                    .avatar_url(&avatar_url(author))
-                    .content(content)
-                    .expect("Invalid webhook content")
-                    .allowed_mentions(Some(&AllowedMentions {
-                        parse: vec![MentionType::Users],
-                        replied_user: false,
-                        ..Default::default()
-                    }))
-                    .await
-                {
-                    tracing::error!("Failed to execute webhook: {err}");
-                };
+                    .description(content)
+                    .color(crate::discord::colours::green())
+                    .build();
+
+                // Only the player's own Discord account is sent their whispers
+                if config.routes.whisper_dms {
+                    if let Some(user) = links::user(player) {
+                        self.send_dm(user, embed).await;
+                    }
+                }
             }
 
             ChatEvent::Toggle(events::Toggle { member, online }) => {
-                let webhook = self.get_webhook(Chat::Guild).await;
-
                 let embed = EmbedBuilder::new()
                     .description(format!(
                         "{member} {status}.",
                         status = if online { "joined" } else { "left" }
                     ))
                     .color(if online {
-                        crate::discord::colours::GREEN
+                        crate::discord::colours::green()
                     } else {
-                        crate::discord::colours::RED
+                        crate::discord::colours::red()
                     })
                     .build();
 
-                if let Err(err) = self
-                    .http
-                    .execute_webhook(
-                        webhook.id,
-                        webhook.token.as_ref().expect("Webhook has no token"),
-                    )
-                    .username(member)
-                    .expect("Invalid webhook username")
-                    .avatar_url(&avatar_url(member))
-                    .embeds(&[embed])
-                    .expect("Invalid webhook embeds")
-                    .allowed_mentions(Some(&AllowedMentions {
-                        parse: vec![MentionType::Users],
-                        replied_user: false,
-                        ..Default::default()
-                    }))
-                    .await
-                {
-                    tracing::error!("Failed to execute webhook: {err}");
-                };
+                for channel in channels {
+                    self.send_as(channel, member, None, &[embed.clone()], &[])
+                        .await;
+                }
             }
 
             ChatEvent::GuildEvent(update) => {
@@ -118,7 +139,7 @@ impl MinecraftHandler {
                                 .build(),
                         )
                         .description(format!("`{member}` joined the guild"))
-                        .color(crate::discord::colours::GREEN)
+                        .color(crate::discord::colours::green())
                         .build(),
                     Leave(member) => EmbedBuilder::new()
                         .author(
@@ -127,7 +148,7 @@ impl MinecraftHandler {
                                 .build(),
                         )
                         .description(format!("`{member}` left the guild"))
-                        .color(crate::discord::colours::RED)
+                        .color(crate::discord::colours::red())
                         .build(),
                     Kick { member, by } => EmbedBuilder::new()
                         .author(
@@ -136,7 +157,7 @@ impl MinecraftHandler {
                                 .build(),
                         )
                         .description(format!("`{member}` was kicked by `{by}`"))
-                        .color(crate::discord::colours::RED)
+                        .color(crate::discord::colours::red())
                         .build(),
                     Promotion {
                         member,
@@ -146,7 +167,7 @@ impl MinecraftHandler {
                         .description(format!(
                             "`{member}` has been promoted from `{old_rank}` to `{new_rank}`",
                         ))
-                        .color(crate::discord::colours::GREEN)
+                        .color(crate::discord::colours::green())
                         .build(),
                     Demotion {
                         member,
@@ -156,12 +177,12 @@ impl MinecraftHandler {
                         .description(format!(
                             "`{member}` has been demoted from `{old_rank}` to `{new_rank}`",
                         ))
-                        .color(crate::discord::colours::RED)
+                        .color(crate::discord::colours::red())
                         .build(),
                 };
 
-                for id in [Chat::Guild, Chat::Officer] {
-                    self.send_embed(Id::new(id.into()), embed.clone()).await;
+                for channel in channels {
+                    self.send_embed(channel, embed.clone()).await;
                 }
             }
 
@@ -183,11 +204,11 @@ impl MinecraftHandler {
                             .description(format!(
                                 "`{member}` has been muted by `{by}` for `{length} {unit}`"
                             ))
-                            .color(crate::discord::colours::RED)
+                            .color(crate::discord::colours::red())
                             .build(),
                         Unmute { by, .. } => EmbedBuilder::new()
                             .description(format!("`{member}` has been unmuted by `{by}`"))
-                            .color(crate::discord::colours::GREEN)
+                            .color(crate::discord::colours::green())
                             .build(),
                     }
                 } else {
@@ -201,27 +222,18 @@ impl MinecraftHandler {
                             .description(format!(
                                 "The guild chat has been muted by `{by}` for `{length} {unit}`"
                             ))
-                            .color(crate::discord::colours::RED)
+                            .color(crate::discord::colours::red())
                             .build(),
                         Unmute { by, .. } => EmbedBuilder::new()
                             .author(EmbedAuthorBuilder::new("Guild Unmuted!").build())
                             .description(format!("The guild chat has been unmuted by `{by}`"))
-                            .color(crate::discord::colours::GREEN)
+                            .color(crate::discord::colours::green())
                             .build(),
                     }
                 };
 
-                match member {
-                    Some(_) => {
-                        // Send only to officer chat
-                        self.send_embed(Id::new(Chat::Officer.into()), embed).await;
-                    }
-                    None => {
-                        // Send to guild and officer chat
-                        for id in [Chat::Guild, Chat::Officer] {
-                            self.send_embed(Id::new(id.into()), embed.clone()).await;
-                        }
-                    }
+                for channel in channels {
+                    self.send_embed(channel, embed.clone()).await;
                 }
             }
 
@@ -229,6 +241,45 @@ impl MinecraftHandler {
         }
     }
 
+    /// Send a message through the channel's webhook, appearing as the player.
+    /// Only the `mentioned` users are pinged
+    async fn send_as(
+        &self,
+        channel: Id<ChannelMarker>,
+        ign: &str,
+        content: Option<&str>,
+        embeds: &[Embed],
+        mentioned: &[Id<UserMarker>],
+    ) {
+        let webhook = self.get_webhook(channel).await;
+        let avatar_url = avatar_url(ign);
+
+        let mut request = self
+            .http
+            .execute_webhook(Id::new(webhook.id), &webhook.token)
+            .username(ign)
+            .expect("Invalid webhook username")
+            .avatar_url(&avatar_url)
+            .embeds(embeds)
+            .expect("Invalid webhook embeds")
+            .allowed_mentions(Some(&AllowedMentions {
+                users: mentioned.to_vec(),
+                replied_user: false,
+                ..Default::default()
+            }));
+
+        if let Some(content) = content {
+            request = request.content(content).expect("Invalid webhook content");
+        }
+
+        if let Err(err) = request.await {
+            tracing::error!("Failed to execute webhook: {err}");
+
+            // The webhook may have been deleted, so look it up again next time
+            storage::write(|data| data.webhooks.remove(&channel.to_string()));
+        };
+    }
+
     async fn send_embed(&self, channel: Id<ChannelMarker>, embed: Embed) {
         if let Err(err) = self
             .http
@@ -241,14 +292,96 @@ impl MinecraftHandler {
         }
     }
 
-    async fn get_webhook(
-        &self,
-        chat: Chat,
-    ) -> dashmap::mapref::one::Ref<Id<ChannelMarker>, Webhook> {
-        self.webhook_cache
-            .get_infallible(&self.http, Id::new(chat.into()), "Bridge")
-            .await
-            .expect("Failed to get webhook")
+    /// Send an embed to a user's DMs
+    async fn send_dm(&self, user: Id<UserMarker>, embed: Embed) {
+        let channel = match self.http.create_private_channel(user).await {
+            Ok(response) => response.model().await,
+            Err(err) => {
+                tracing::error!("Failed to open DM channel: {err}");
+                return;
+            }
+        };
+
+        match channel {
+            Ok(channel) => self.send_embed(channel.id, embed).await,
+            Err(err) => tracing::error!("Failed to read DM channel: {err}"),
+        }
+    }
+
+    /// The webhook used to send messages in the channel, which is remembered between restarts
+    async fn get_webhook(&self, channel: Id<ChannelMarker>) -> StoredWebhook {
+        if let Some(webhook) =
+            storage::read(|data| data.webhooks.get(&channel.to_string()).cloned())
+        {
+            return webhook;
+        }
+
+        let webhook = {
+            let webhook = self
+                .webhook_cache
+                .get_infallible(&self.http, channel, &config().discord.webhook_name)
+                .await
+                .expect("Failed to get webhook");
+
+            StoredWebhook {
+                id: webhook.id.get(),
+                token: webhook.token.clone().expect("Webhook has no token"),
+            }
+        };
+
+        storage::write(|data| data.webhooks.insert(channel.to_string(), webhook.clone()));
+
+        webhook
+    }
+
+    /// Finish linking a Discord user to a player if the player typed their code
+    async fn verify_link(&self, event: ChatEvent<'_>) {
+        let (ChatEvent::Message(Message {
+            author: player,
+            content,
+            chat: Chat::Guild,
+        })
+        | ChatEvent::Whisper(Whisper {
+            player,
+            content,
+            incoming: true,
+        })) = event
+        else {
+            return;
+        };
+
+        if let Some(user) = links::verify(player, content) {
+            tracing::info!("Linked {user} to {player}");
+
+            let embed = EmbedBuilder::new()
+                .description(format!("Your account is now linked to `{player}`"))
+                .color(crate::discord::colours::green())
+                .build();
+
+            self.send_dm(user, embed).await;
+        }
+    }
+
+    /// Keep track of members' ranks, so their roles can be synced
+    fn update_ranks(&self, event: ChatEvent) {
+        use crate::payloads::events::GuildEvent;
+
+        let ChatEvent::GuildEvent(update) = event else {
+            return;
+        };
+
+        match update {
+            GuildEvent::Join(member) => roles::set_rank(member, Some(&config().roles.join_rank)),
+            GuildEvent::Leave(member) | GuildEvent::Kick { member, .. } => {
+                roles::set_rank(member, None)
+            }
+            GuildEvent::Promotion {
+                member, new_rank, ..
+            }
+            | GuildEvent::Demotion {
+                member, new_rank, ..
+            } => roles::set_rank(member, Some(new_rank)),
+        }
     }
 
     fn add_event_to_autocomplete(&self, event: ChatEvent) {
@@ -256,7 +389,11 @@ impl MinecraftHandler {
         use crate::payloads::events::{GuildEvent, Moderation};
 
         match event {
-            ChatEvent::Message(Message { author, .. }) => autocomplete::add_username(author),
+            ChatEvent::Message(Message {
+                author,
+                chat: Chat::Guild | Chat::Officer,
+                ..
+            }) => autocomplete::add_username(author),
             ChatEvent::Toggle(Toggle { member, .. }) => autocomplete::add_username(member),
             ChatEvent::GuildEvent(update) => match update {
                 GuildEvent::Join(member) => autocomplete::add_username(member),
@@ -284,7 +421,53 @@ impl MinecraftHandler {
                     autocomplete::add_username(by);
                 }
             },
-            ChatEvent::CommandResponse(_) | ChatEvent::Unknown(_) => {}
+            // Party members and whisperers aren't necessarily in the guild
+            ChatEvent::Message(_)
+            | ChatEvent::Whisper(_)
+            | ChatEvent::CommandResponse(_)
+            | ChatEvent::Unknown(_) => {}
         }
     }
 }
+
+/// Remember who is muted, so it survives restarts
+fn track_mutes(event: ChatEvent) {
+    use crate::payloads::events::Moderation;
+
+    match event {
+        ChatEvent::Moderation(Moderation::Mute {
+            member: Some(member),
+            length,
+            unit,
+            ..
+        }) => storage::mute(member, unit.duration(length)),
+        ChatEvent::Moderation(Moderation::Unmute {
+            member: Some(member),
+            ..
+        }) => storage::unmute(member),
+        _ => {}
+    }
+}
+
+/// The kind of event, which decides the channels it is sent to
+fn route(event: &ChatEvent) -> Option<Route> {
+    use crate::payloads::events::Moderation;
+
+    match event {
+        ChatEvent::Message(Message { chat, .. }) => Some(Route::Chat(*chat)),
+        ChatEvent::Whisper(Whisper { incoming: true, .. }) => Some(Route::Whisper),
+        ChatEvent::Toggle(_) => Some(Route::Toggle),
+        ChatEvent::GuildEvent(_) => Some(Route::GuildEvent),
+        ChatEvent::Moderation(
+            Moderation::Mute {
+                member: Some(_), ..
+            }
+            | Moderation::Unmute {
+                member: Some(_), ..
+            },
+        ) => Some(Route::MemberModeration),
+        ChatEvent::Moderation(_) => Some(Route::ChatModeration),
+        // Whispers sent by the bot are only echoes of Discord messages
+        ChatEvent::Whisper(_) | ChatEvent::CommandResponse(_) | ChatEvent::Unknown(_) => None,
+    }
+}
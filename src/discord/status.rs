@@ -1,7 +1,10 @@
 use super::colours;
 use crate::{config, Error};
 use once_cell::sync::OnceCell;
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use twilight_model::{id::Id, util::Timestamp};
 use twilight_util::builder::embed::{EmbedAuthorBuilder, EmbedBuilder};
 
@@ -17,6 +20,14 @@ pub enum Status<'a> {
     Connected(String),
     /// The bridge has been disconnected from
     Disconnected(String),
+    /// The bridge is about to try reconnecting to the server
+    Reconnecting {
+        attempt: u32,
+        max_attempts: u32,
+        delay: Duration,
+    },
+    /// The bridge has given up on reconnecting to the server
+    GaveUp(u32),
 }
 
 pub use Status::*;
@@ -28,7 +39,7 @@ pub async fn send(status: Status<'_>) {
             let online = EmbedBuilder::new()
                 .author(EmbedAuthorBuilder::new("Chat Bridge is Online").build())
                 .timestamp(get_current_timestamp())
-                .color(colours::GREEN);
+                .color(colours::green());
 
             (online.clone(), online)
         }
@@ -36,7 +47,7 @@ pub async fn send(status: Status<'_>) {
             let base = EmbedBuilder::new()
                 .author(EmbedAuthorBuilder::new("Chat Bridge is Offline").build())
                 .timestamp(get_current_timestamp())
-                .color(colours::RED);
+                .color(colours::red());
 
             (
                 base.clone(),
@@ -45,6 +56,7 @@ pub async fn send(status: Status<'_>) {
                     cause = match error {
                         Error::Config(_err) =>
                             unreachable!("Config errors are handled at the start of execution"),
+                        Error::Storage(err) => err.to_string(),
                         Error::Join(err) => err.to_string(),
                         Error::Discord(err) => err.to_string(),
                         Error::Terminated => "Process terminated by user".to_string(),
@@ -56,9 +68,12 @@ pub async fn send(status: Status<'_>) {
         Status::Connected(ign) => {
             let base = EmbedBuilder::new()
                 .author(EmbedAuthorBuilder::new("Minecraft Bot is Connected").build())
-                .description(format!("Connected to `todo` as `{ign}`"))
+                .description(format!(
+                    "Connected to `{server}` as `{ign}`",
+                    server = config().server_address
+                ))
                 .timestamp(get_current_timestamp())
-                .color(colours::GREEN);
+                .color(colours::green());
 
             (base.clone(), base)
         }
@@ -67,10 +82,37 @@ pub async fn send(status: Status<'_>) {
                 .author(EmbedAuthorBuilder::new("Minecraft Bot is Disconnected").build())
                 .description("I have been kicked from the server, attempting to reconnect")
                 .timestamp(get_current_timestamp())
-                .color(colours::YELLOW);
+                .color(colours::yellow());
 
             (base.clone(), base.description(format!("Reason: {reason}")))
         }
+        Status::Reconnecting {
+            attempt,
+            max_attempts,
+            delay,
+        } => {
+            let base = EmbedBuilder::new()
+                .author(EmbedAuthorBuilder::new("Minecraft Bot is Reconnecting").build())
+                .description(format!(
+                    "Attempt {attempt}/{max_attempts} in {seconds}s",
+                    seconds = delay.as_secs()
+                ))
+                .timestamp(get_current_timestamp())
+                .color(colours::yellow());
+
+            (base.clone(), base)
+        }
+        Status::GaveUp(attempts) => {
+            let base = EmbedBuilder::new()
+                .author(EmbedAuthorBuilder::new("Minecraft Bot is Disconnected").build())
+                .description(format!(
+                    "Gave up on reconnecting after {attempts} attempts, messages won't be sent until the bridge is restarted"
+                ))
+                .timestamp(get_current_timestamp())
+                .color(colours::red());
+
+            (base.clone(), base)
+        }
     };
 
     for (channel_id, embed) in [
@@ -89,7 +131,7 @@ pub async fn send(status: Status<'_>) {
     }
 }
 
-fn get_current_timestamp() -> Timestamp {
+pub(super) fn get_current_timestamp() -> Timestamp {
     Timestamp::from_secs(
         SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
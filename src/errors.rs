@@ -4,6 +4,10 @@ pub enum Error {
     #[error(transparent)]
     Config(#[from] crate::config::EnvError),
 
+    // Storage
+    #[error(transparent)]
+    Storage(#[from] crate::storage::LoadError),
+
     // Minecraft
     #[error(transparent)]
     Join(#[from] azalea::StartError),
@@ -5,6 +5,7 @@ mod errors;
 mod minecraft;
 mod payloads;
 mod sanitizer;
+mod storage;
 
 pub use config::config;
 use discord::status;
@@ -14,11 +15,27 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 #[tokio::main]
 async fn main() -> errors::Result<()> {
     tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
+        .with(fmt::layer().with_filter(EnvFilter::from_default_env()))
+        .with(discord::logger::layer())
         .init();
     dotenvy::dotenv().ok();
-    config::init(config::Config::new_from_env()?);
+    config::init(config::Config::load()?);
+    storage::init()?;
+    tokio::spawn(storage::run());
+
+    // Reload the config when SIGHUP is received
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
+
+        while hangup.recv().await.is_some() {
+            if let Err(e) = discord::reload() {
+                tracing::error!("Failed to reload the config: {e}");
+            }
+        }
+    });
 
     #[cfg(debug_assertions)]
     {
@@ -60,6 +77,7 @@ async fn main() -> errors::Result<()> {
     .expect_err("Bridge can only exit with an error");
 
     status::send(status::Offline(&reason)).await;
+    storage::flush().await;
     tracing::error!("{reason}");
 
     Err(reason)
@@ -0,0 +1,145 @@
+use crate::config::Reconnect;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant},
+};
+
+/// Exponential backoff with jitter between reconnection attempts.
+///
+/// The delay doubles with every attempt, starting at `base_delay` and never passing `max_delay`. Half of each
+/// delay is fixed and the other half is random, so a restarted server isn't hit by every bot at once.
+/// The settings are passed in every time rather than kept, so a reload applies to the next attempt.
+///
+/// Attempts carry on across disconnects, and only start over once a connection has stayed up for `stable_after`,
+/// so a server which kicks the bot straight after it joins still runs out the attempts.
+#[derive(Debug, Default)]
+pub struct Backoff {
+    attempt: u32,
+    /// When the current connection was made, if there is one
+    connected: Option<Instant>,
+}
+
+impl Backoff {
+    pub fn connected(&mut self, now: Instant) {
+        self.connected = Some(now);
+    }
+
+    /// Start over from the first attempt if the connection which was lost had stayed up long enough
+    pub fn disconnected(&mut self, now: Instant, settings: &Reconnect) {
+        if self
+            .connected
+            .take()
+            .is_some_and(|connected| now.duration_since(connected) >= settings.stable_after)
+        {
+            self.attempt = 0;
+        }
+    }
+
+    /// The number of attempts made so far
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Start another attempt, returning how long to wait before it, or `None` if every attempt has been used
+    pub fn next_delay(&mut self, settings: &Reconnect) -> Option<Duration> {
+        if self.attempt >= settings.max_attempts {
+            return None;
+        }
+
+        let delay = settings
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(self.attempt))
+            .min(settings.max_delay.max(settings.base_delay));
+        self.attempt += 1;
+
+        Some(delay / 2 + jitter(delay / 2))
+    }
+}
+
+/// A random duration between zero and `max`
+fn jitter(max: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn settings(base: u64, max: u64, max_attempts: u32, stable_after: u64) -> Reconnect {
+        Reconnect {
+            base_delay: Duration::from_secs(base),
+            max_delay: Duration::from_secs(max),
+            max_attempts,
+            stable_after: Duration::from_secs(stable_after),
+        }
+    }
+
+    #[test]
+    fn doubles() {
+        let settings = settings(4, 60, 10, 0);
+        let mut backoff = Backoff::default();
+
+        for expected in [4, 8, 16, 32, 60, 60] {
+            let delay = backoff.next_delay(&settings).unwrap();
+            let expected = Duration::from_secs(expected);
+
+            assert!(delay >= expected / 2 && delay <= expected, "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn gives_up() {
+        let settings = settings(1, 1, 3, 0);
+        let mut backoff = Backoff::default();
+
+        for _ in 0..3 {
+            assert!(backoff.next_delay(&settings).is_some());
+        }
+
+        assert_eq!(backoff.attempt(), 3);
+        assert_eq!(backoff.next_delay(&settings), None);
+    }
+
+    #[test]
+    fn reloaded() {
+        let mut backoff = Backoff::default();
+        backoff.next_delay(&settings(1, 1, 1, 0));
+
+        assert_eq!(backoff.next_delay(&settings(1, 1, 1, 0)), None);
+        // Raising the limit gives more attempts, carrying on from the same number
+        let delay = backoff.next_delay(&settings(10, 60, 5, 0)).unwrap();
+        assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(20));
+    }
+
+    #[test_case(30, 2 ; "Kicked straight away")]
+    #[test_case(60, 0 ; "Stayed up")]
+    fn stable(uptime: u64, attempt: u32) {
+        let settings = settings(1, 1, 3, 60);
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+
+        backoff.next_delay(&settings);
+        backoff.connected(now);
+        backoff.disconnected(now + Duration::from_secs(uptime), &settings);
+        backoff.next_delay(&settings);
+        backoff.connected(now);
+        backoff.disconnected(now + Duration::from_secs(uptime), &settings);
+
+        assert_eq!(backoff.attempt(), attempt);
+    }
+
+    #[test]
+    fn never_connected() {
+        let settings = settings(1, 1, 3, 0);
+        let mut backoff = Backoff::default();
+
+        backoff.next_delay(&settings);
+        backoff.disconnected(Instant::now(), &settings);
+
+        assert_eq!(backoff.attempt(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+use super::{ChatQueue, QueuedCommand};
+use crate::{
+    config,
+    payloads::{command::Priority, events::Location},
+};
+use azalea::{
+    chat::ChatReceivedEvent, ecs::prelude::*, packet_handling::game::PacketEvent,
+    protocol::packets::game::ClientboundGamePacket,
+};
+use parking_lot::{const_rwlock, RwLock};
+
+static LOCATION: RwLock<Location> = const_rwlock(Location::Unknown);
+
+/// Where on the server the bot was last seen
+pub fn current_location() -> Location {
+    *LOCATION.read()
+}
+
+/// Forget where the bot is, used once it disconnects
+pub(super) fn reset() {
+    *LOCATION.write() = Location::Unknown;
+}
+
+/// Follow the bot between servers, running the configured commands whenever it ends up away from home
+pub(super) fn track_location(
+    mut packets: EventReader<PacketEvent>,
+    mut chats: EventReader<ChatReceivedEvent>,
+    mut queue: ResMut<ChatQueue>,
+) {
+    for event in packets.read() {
+        // Joining or switching servers can land the bot anywhere, so wait for a message saying where
+        if let ClientboundGamePacket::Login(_) | ClientboundGamePacket::Respawn(_) = *event.packet {
+            move_to(Location::Unknown, &mut queue);
+        }
+    }
+
+    for event in chats.read() {
+        if let Ok(location) = Location::try_from(event.packet.content().as_str()) {
+            move_to(location, &mut queue);
+        }
+    }
+}
+
+fn move_to(location: Location, queue: &mut ChatQueue) {
+    let previous = std::mem::replace(&mut *LOCATION.write(), location);
+
+    if previous == location {
+        return;
+    }
+
+    tracing::info!("Moved from {previous} to {location}");
+
+    if location != Location::Unknown && location != config().home {
+        for command in &config().return_commands {
+            tracing::debug!("Returning to {home} with `{command}`", home = config().home);
+
+            queue.messages.push_back(
+                Priority::Moderation,
+                QueuedCommand {
+                    command: command.clone(),
+                    priority: Priority::Moderation,
+                    notify: None,
+                    retries: 0,
+                },
+            );
+        }
+    }
+}
@@ -1,9 +1,19 @@
+mod backoff;
+mod location;
 mod mpsc_adapter;
+mod priority_queue;
+mod rate_limit;
 pub mod swarm;
 
-use crate::payloads::{
-    command::{CommandPayload, MinecraftCommand},
-    events::RawChatEvent,
+pub use location::current_location;
+
+use crate::{
+    config,
+    payloads::{
+        command::{CommandPayload, NotSent, Priority},
+        events::{RawChatEvent, Response},
+    },
+    sanitizer::length::{self, Length},
 };
 use azalea::{
     app::{Plugin, Update},
@@ -17,10 +27,21 @@ use azalea::{
 };
 use once_cell::sync::OnceCell;
 use parking_lot::{Mutex, RwLock};
-use std::{collections::VecDeque, sync::Arc};
+use priority_queue::PriorityQueue;
+use rate_limit::RateLimiter;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use tokio::sync::{mpsc, oneshot};
 
 pub static USERNAME: OnceCell<RwLock<String>> = OnceCell::new();
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the bot is currently logged in to the server
+pub fn is_connected() -> bool {
+    CONNECTED.load(Ordering::Relaxed)
+}
 
 type Sender = async_broadcast::Sender<RawChatEvent>;
 type Receiver = Arc<Mutex<mpsc::UnboundedReceiver<CommandPayload>>>;
@@ -41,14 +62,18 @@ impl Plugin for MinecraftBridgePlugin {
             Update,
             (
                 handle_incoming_chats,
+                retry_rate_limited_commands,
                 handle_outgoing_commands,
                 update_username,
+                location::track_location,
                 drain_message_queue,
             ),
         );
 
         app.insert_resource(ChatQueue {
-            messages: VecDeque::new(),
+            messages: PriorityQueue::default(),
+            last_sent: None,
+            limiter: RateLimiter::new(config().rate_limit.burst, config().rate_limit.refill_ticks),
             ticks: 0,
         });
     }
@@ -81,46 +106,56 @@ fn handle_incoming_chats(
 
 #[derive(Resource)]
 struct ChatQueue {
-    pub messages: VecDeque<(String, oneshot::Sender<()>)>,
+    pub messages: PriorityQueue<QueuedCommand>,
+    /// The most recently sent command, kept for [`RETRY_WINDOW`] ticks so it can be retried if Hypixel rejects it
+    pub last_sent: Option<LastSent>,
+    pub limiter: RateLimiter,
+    /// Ticks to pause for before sending anything, used to back off before a retry
+    pub ticks: usize,
+}
+
+struct QueuedCommand {
+    pub command: String,
+    pub priority: Priority,
+    /// Taken once the command is first sent, so retries don't notify twice
+    pub notify: Option<oneshot::Sender<Result<(), NotSent>>>,
+    pub retries: u32,
+}
+
+struct LastSent {
+    pub queued: QueuedCommand,
+    /// Ticks since the command was sent
     pub ticks: usize,
 }
 
 fn handle_outgoing_commands(mut reader: EventReader<CommandPayload>, mut queue: ResMut<ChatQueue>) {
     for event in reader.read() {
-        use MinecraftCommand::*;
+        let command = event.command.to_string();
 
-        let command = match &event.command {
-            ChatMessage(author, message, chat) => {
-                format!("/{prefix} {author}: {message}", prefix = chat.prefix())
-            }
-            Mute(player, duration, unit) => {
-                format!(
-                    "/g mute {player} {duration}{unit}",
-                    unit = char::from(*unit)
-                )
-            }
-            Unmute(player) => format!("/g unmute {player}"),
-            Invite(player) => format!("/g invite {player}"),
-            Kick(player, reason) => format!("/g kick {player} {reason}"),
-            Demote(player) => format!("/g demote {player}"),
-            Promote(player) => format!("/g promote {player}"),
-            SetRank(player, rank) => format!("/g setrank {player} {rank}"),
-            Execute(command) => format!("/{command}"),
-        };
+        if let Err(overflow) = length::fit(&command, Length::default()) {
+            tracing::error!("Not sending a command which is {overflow}: {command}");
 
-        assert!(command.len() <= 256, "Command too long: {command}");
+            if let Some(notify) = event.notify.lock().take() {
+                // Nobody is waiting if the command was already cancelled
+                notify.send(Err(NotSent::TooLong)).ok();
+            }
+            continue;
+        }
 
         tracing::debug!("Sending to Minecraft: {}", command);
 
-        queue.messages.push_back((
-            command,
-            event.notify.lock().take().expect("Notify was None"),
-        ));
+        queue.messages.push_back(
+            event.priority,
+            QueuedCommand {
+                command,
+                priority: event.priority,
+                notify: Some(event.notify.lock().take().expect("Notify was None")),
+                retries: 0,
+            },
+        );
     }
 }
 
-const DELAY_BETWEEN_MESSAGES: usize = 5;
-
 fn drain_message_queue(
     mut queue: ResMut<ChatQueue>,
     mut query: Query<Entity, With<LocalEntity>>,
@@ -130,23 +165,104 @@ fn drain_message_queue(
         return;
     };
 
+    // Pick up changes from a config reload
+    queue
+        .limiter
+        .configure(config().rate_limit.burst, config().rate_limit.refill_ticks);
+    queue.limiter.tick();
+
+    // Hypixel rejects a command straight away, so a later rejection is for something else
+    if let Some(last_sent) = &mut queue.last_sent {
+        last_sent.ticks += 1;
+
+        if last_sent.ticks > RETRY_WINDOW {
+            queue.last_sent = None;
+        }
+    }
+
     if queue.ticks > 0 {
         return queue.ticks -= 1;
     }
 
-    let Some((message, notify)) = queue.messages.pop_front() else {
+    // Nobody is waiting for commands which were cancelled before they were sent, such as deleted messages
+    queue.messages.retain(|queued| {
+        queued
+            .notify
+            .as_ref()
+            .is_none_or(|notify| !notify.is_closed())
+    });
+
+    if queue.messages.is_empty() || !queue.limiter.try_acquire() {
         return;
-    };
+    }
 
-    // Wait [`DELAY_BETWEEN_MESSAGES`] ticks between messages
-    queue.ticks += DELAY_BETWEEN_MESSAGES;
+    let mut queued = queue
+        .messages
+        .pop()
+        .expect("Queue was checked to be non-empty");
 
     writer.send(SendChatEvent {
         entity,
-        content: message.clone(),
+        content: queued.command.clone(),
     });
 
-    notify
-        .send(())
-        .expect("Minecraft command verifier receiver was dropped");
+    if let Some(notify) = queued.notify.take() {
+        notify
+            .send(Ok(()))
+            .expect("Minecraft command verifier receiver was dropped");
+    }
+
+    queue.last_sent = Some(LastSent { queued, ticks: 0 });
+}
+
+const MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: usize = 20;
+/// How many ticks after sending a command Hypixel can still reject it for being sent too fast
+const RETRY_WINDOW: usize = 20;
+
+/// Put the last sent command back at the front of the queue when Hypixel says we're sending commands too fast,
+/// waiting [`RETRY_BASE_DELAY`] ticks and doubling the delay for every subsequent retry
+fn retry_rate_limited_commands(
+    mut reader: EventReader<ChatReceivedEvent>,
+    mut queue: ResMut<ChatQueue>,
+) {
+    for event in reader.read() {
+        match Response::try_from(event.packet.content().as_str()) {
+            Ok(Response::TooFast) => {}
+            // Any other response means the last command went through
+            Ok(_) => {
+                queue.last_sent = None;
+                continue;
+            }
+            Err(_) => continue,
+        }
+
+        queue.limiter.tighten();
+
+        let Some(LastSent { mut queued, .. }) = queue.last_sent.take() else {
+            tracing::warn!("Sending commands too fast, but no command was sent");
+            continue;
+        };
+
+        if queued.retries >= MAX_RETRIES {
+            tracing::warn!(
+                "Giving up on `{}` after {} retries",
+                queued.command,
+                queued.retries
+            );
+            continue;
+        }
+
+        queued.retries += 1;
+        let delay = RETRY_BASE_DELAY * 2usize.pow(queued.retries - 1);
+
+        tracing::debug!(
+            "Sending commands too fast, retrying `{}` in {delay} ticks (attempt {}/{MAX_RETRIES})",
+            queued.command,
+            queued.retries
+        );
+
+        queue.ticks = queue.ticks.max(delay);
+        queue.messages.push_front(queued.priority, queued);
+    }
 }
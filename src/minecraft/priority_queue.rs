@@ -0,0 +1,128 @@
+use crate::payloads::command::Priority;
+use std::collections::VecDeque;
+use strum::EnumCount;
+
+/// A queue with a lane for each [`Priority`], which serves the most urgent lane first.
+///
+/// To stop busy lanes from starving the less urgent ones, a lane which has been passed over
+/// [`MAX_SKIPPED`] times is served next regardless of its priority.
+pub struct PriorityQueue<T> {
+    lanes: [VecDeque<T>; Priority::COUNT],
+    /// The number of times each lane has been passed over while it had something waiting
+    skipped: [usize; Priority::COUNT],
+}
+
+const MAX_SKIPPED: usize = 8;
+
+impl<T> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self {
+            lanes: std::array::from_fn(|_| VecDeque::new()),
+            skipped: [0; Priority::COUNT],
+        }
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    pub fn push_back(&mut self, priority: Priority, item: T) {
+        self.lanes[priority as usize].push_back(item);
+    }
+
+    /// Put an item at the front of its lane, so it is the next to be sent from that lane
+    pub fn push_front(&mut self, priority: Priority, item: T) {
+        self.lanes[priority as usize].push_front(item);
+    }
+
+    /// Keep only the items `f` returns `true` for, in the same order
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        for lane in &mut self.lanes {
+            lane.retain(&mut f);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(VecDeque::is_empty)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let starving = (0..Priority::COUNT)
+            .find(|&lane| !self.lanes[lane].is_empty() && self.skipped[lane] >= MAX_SKIPPED);
+        let lane =
+            starving.or_else(|| (0..Priority::COUNT).find(|&lane| !self.lanes[lane].is_empty()))?;
+
+        for (other, skipped) in self.skipped.iter_mut().enumerate() {
+            if other == lane {
+                *skipped = 0;
+            } else if !self.lanes[other].is_empty() {
+                *skipped += 1;
+            }
+        }
+
+        self.lanes[lane].pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_urgent_first() {
+        let mut queue = PriorityQueue::default();
+
+        queue.push_back(Priority::Bulk, "bulk");
+        queue.push_back(Priority::Guild, "guild");
+        queue.push_back(Priority::Moderation, "moderation");
+        queue.push_back(Priority::Officer, "officer");
+
+        assert_eq!(queue.pop(), Some("moderation"));
+        assert_eq!(queue.pop(), Some("officer"));
+        assert_eq!(queue.pop(), Some("guild"));
+        assert_eq!(queue.pop(), Some("bulk"));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn fifo_within_lane() {
+        let mut queue = PriorityQueue::default();
+
+        queue.push_back(Priority::Guild, 1);
+        queue.push_back(Priority::Guild, 2);
+        queue.push_front(Priority::Guild, 0);
+
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+    }
+
+    #[test]
+    fn retain() {
+        let mut queue = PriorityQueue::default();
+
+        queue.push_back(Priority::Guild, 1);
+        queue.push_back(Priority::Guild, 2);
+        queue.push_back(Priority::Bulk, 3);
+        queue.retain(|item| *item != 2);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(3));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn no_starvation() {
+        let mut queue = PriorityQueue::default();
+
+        queue.push_back(Priority::Bulk, "bulk");
+        for _ in 0..MAX_SKIPPED * 2 {
+            queue.push_back(Priority::Guild, "guild");
+        }
+
+        let position = std::iter::from_fn(|| queue.pop())
+            .position(|item| item == "bulk")
+            .expect("Bulk item was never served");
+
+        assert_eq!(position, MAX_SKIPPED);
+    }
+}
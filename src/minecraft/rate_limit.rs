@@ -0,0 +1,188 @@
+/// A token bucket limiting how often commands are sent to Minecraft.
+///
+/// Every command costs a token, and a token is refilled every `refill` ticks up to `burst`.
+/// When Hypixel says we're sending commands too fast the refill interval is doubled, and after
+/// [`QUIET_PERIOD`] ticks without being limited it is halved again until it is back to the configured rate.
+#[derive(Debug)]
+pub struct RateLimiter {
+    burst: u32,
+    tokens: u32,
+    base_refill: u32,
+    refill: u32,
+    /// Ticks since the last token was refilled
+    elapsed: u32,
+    /// Ticks since Hypixel last limited us
+    quiet: u32,
+}
+
+/// The number of ticks without being limited before the refill interval is relaxed
+const QUIET_PERIOD: u32 = 20 * 30;
+/// The refill interval will never be tightened past this many ticks
+const MAX_REFILL: u32 = 20 * 4;
+
+impl RateLimiter {
+    pub fn new(burst: u32, refill: u32) -> Self {
+        let burst = burst.max(1);
+        let refill = refill.max(1);
+
+        Self {
+            burst,
+            tokens: burst,
+            base_refill: refill,
+            refill,
+            elapsed: 0,
+            quiet: 0,
+        }
+    }
+
+    /// Advance the limiter by one tick
+    pub fn tick(&mut self) {
+        if self.tokens < self.burst {
+            self.elapsed += 1;
+
+            if self.elapsed >= self.refill {
+                self.elapsed = 0;
+                self.tokens += 1;
+            }
+        }
+
+        if self.refill > self.base_refill {
+            self.quiet += 1;
+
+            if self.quiet >= QUIET_PERIOD {
+                self.quiet = 0;
+                self.refill = (self.refill / 2).max(self.base_refill);
+
+                tracing::debug!("Relaxed rate limit to 1 command per {} ticks", self.refill);
+            }
+        }
+    }
+
+    /// Take a token if one is available, returning whether a command can be sent
+    pub fn try_acquire(&mut self) -> bool {
+        if self.tokens == 0 {
+            return false;
+        }
+
+        self.tokens -= 1;
+        true
+    }
+
+    /// Change the configured rate, keeping any slowdown from being limited
+    pub fn configure(&mut self, burst: u32, refill: u32) {
+        let burst = burst.max(1);
+        let refill = refill.max(1);
+
+        if burst == self.burst && refill == self.base_refill {
+            return;
+        }
+
+        self.refill = if self.refill > self.base_refill {
+            self.refill.max(refill)
+        } else {
+            refill
+        };
+        self.base_refill = refill;
+        self.burst = burst;
+        self.tokens = self.tokens.min(burst);
+
+        tracing::debug!(
+            "Rate limit configured to {burst} commands, refilling every {refill} ticks"
+        );
+    }
+
+    /// Slow down after Hypixel says we're sending commands too fast
+    pub fn tighten(&mut self) {
+        self.tokens = 0;
+        self.elapsed = 0;
+        self.quiet = 0;
+        self.refill = (self.refill * 2).min(MAX_REFILL.max(self.base_refill));
+
+        tracing::debug!(
+            "Tightened rate limit to 1 command per {} ticks",
+            self.refill
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks_until_acquired(limiter: &mut RateLimiter) -> u32 {
+        let mut ticks = 0;
+
+        while !limiter.try_acquire() {
+            limiter.tick();
+            ticks += 1;
+        }
+
+        ticks
+    }
+
+    #[test]
+    fn burst() {
+        let mut limiter = RateLimiter::new(3, 5);
+
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        assert_eq!(ticks_until_acquired(&mut limiter), 5);
+    }
+
+    #[test]
+    fn tighten() {
+        let mut limiter = RateLimiter::new(3, 5);
+
+        limiter.tighten();
+        assert_eq!(ticks_until_acquired(&mut limiter), 10);
+
+        limiter.tighten();
+        assert_eq!(ticks_until_acquired(&mut limiter), 20);
+    }
+
+    #[test]
+    fn configure() {
+        let mut limiter = RateLimiter::new(3, 5);
+
+        limiter.configure(1, 2);
+        assert!(limiter.try_acquire());
+        assert_eq!(ticks_until_acquired(&mut limiter), 2);
+
+        limiter.tighten();
+        limiter.configure(1, 3);
+        assert_eq!(ticks_until_acquired(&mut limiter), 4);
+    }
+
+    #[test]
+    fn tighten_is_capped() {
+        let mut limiter = RateLimiter::new(1, 5);
+
+        for _ in 0..10 {
+            limiter.tighten();
+        }
+
+        assert_eq!(ticks_until_acquired(&mut limiter), MAX_REFILL);
+    }
+
+    #[test]
+    fn relax() {
+        let mut limiter = RateLimiter::new(1, 5);
+
+        limiter.tighten();
+        limiter.tighten();
+
+        for _ in 0..QUIET_PERIOD {
+            limiter.tick();
+        }
+        limiter.try_acquire();
+        assert_eq!(ticks_until_acquired(&mut limiter), 10);
+
+        for _ in 0..QUIET_PERIOD {
+            limiter.tick();
+        }
+        limiter.try_acquire();
+        assert_eq!(ticks_until_acquired(&mut limiter), 5);
+    }
+}
@@ -1,5 +1,12 @@
+use super::{backoff::Backoff, location, CONNECTED};
 use crate::{config, discord::status, minecraft::MinecraftBridgePlugin};
 use azalea::{prelude::*, swarm::prelude::*, ClientInformation, StartError};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{sync::atomic::Ordering, time::Instant};
+
+/// Kept across disconnects, so a connection that keeps dropping doesn't get a fresh set of attempts every time
+static BACKOFF: Lazy<Mutex<Backoff>> = Lazy::new(|| Mutex::new(Backoff::default()));
 
 pub async fn run(
     account: Account,
@@ -49,6 +56,8 @@ async fn handle(bot: Client, event: Event, _state: State) -> anyhow::Result<()>
                 config().server_port,
                 bot.profile.name,
             );
+            CONNECTED.store(true, Ordering::Relaxed);
+            BACKOFF.lock().connected(Instant::now());
             status::send(status::Connected(bot.profile.name.clone())).await;
         }
         Event::Packet(packet) => {
@@ -75,10 +84,52 @@ async fn handle_swarm(
     match event {
         SwarmEvent::Init => status::send(status::Online).await,
         SwarmEvent::Disconnect(account, _) => {
-            swarm.add_and_retry_forever(&account, State).await;
+            CONNECTED.store(false, Ordering::Relaxed);
+            location::reset();
+            reconnect(&mut swarm, &account).await;
         }
         _ => {}
     }
 
     Ok(())
 }
+
+/// Try to rejoin the server, backing off between attempts until the configured number of attempts is used up.
+/// A successful attempt is announced by the `Connected` status sent on login.
+async fn reconnect(swarm: &mut Swarm, account: &Account) {
+    BACKOFF
+        .lock()
+        .disconnected(Instant::now(), &config().reconnect);
+
+    loop {
+        // Read every attempt, so a reload applies to the next one
+        let config = config();
+        let (delay, attempt) = {
+            let mut backoff = BACKOFF.lock();
+
+            (backoff.next_delay(&config.reconnect), backoff.attempt())
+        };
+        let max_attempts = config.reconnect.max_attempts;
+
+        let Some(delay) = delay else {
+            tracing::error!("Giving up on reconnecting after {attempt} attempts");
+            status::send(status::GaveUp(attempt)).await;
+            return;
+        };
+
+        tracing::info!("Reconnecting in {delay:?} (attempt {attempt}/{max_attempts})");
+        status::send(status::Reconnecting {
+            attempt,
+            max_attempts,
+            delay,
+        })
+        .await;
+
+        tokio::time::sleep(delay).await;
+
+        match swarm.add(account, State).await {
+            Ok(_) => return,
+            Err(e) => tracing::warn!("Reconnection attempt {attempt} failed: {e}"),
+        }
+    }
+}
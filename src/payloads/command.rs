@@ -6,31 +6,59 @@ use crate::{
 use azalea::{ecs::prelude::*, prelude::*};
 use parking_lot::Mutex;
 use std::sync::Arc;
+use strum::EnumCount;
 use tokio::sync::oneshot;
 
-pub type Notifier = Arc<Mutex<Option<oneshot::Sender<()>>>>;
+/// Told once whether the command was sent
+pub type Notifier = Arc<Mutex<Option<oneshot::Sender<Result<(), NotSent>>>>>;
+
+/// Why Minecraft didn't send a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotSent {
+    /// The command is longer than Minecraft allows
+    TooLong,
+    /// Minecraft stopped without answering, such as when the bot shuts down
+    Dropped,
+}
 
 /// A Payload sent to Minecraft
 #[derive(Event, Debug)]
 #[non_exhaustive]
 pub struct CommandPayload {
     pub command: MinecraftCommand,
+    pub priority: Priority,
     pub notify: Notifier,
 }
 
 impl CommandPayload {
-    pub fn new(command: MinecraftCommand, sender: oneshot::Sender<()>) -> Self {
+    pub fn new(command: MinecraftCommand, sender: oneshot::Sender<Result<(), NotSent>>) -> Self {
         Self {
+            priority: command.priority(),
             command,
             notify: Arc::new(Mutex::new(Some(sender))),
         }
     }
 }
 
-#[derive(Debug)]
+/// How urgently a command should be sent, from most to least urgent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumCount)]
+pub enum Priority {
+    /// Guild moderation commands
+    Moderation,
+    /// Messages to the officer chat
+    Officer,
+    /// Messages to the guild chat, party chat or a player
+    Guild,
+    /// Anything else
+    Bulk,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum MinecraftCommand {
-    /// A message to the guild or officer chat
+    /// A message to the guild, officer or party chat
     ChatMessage(CleanString, CleanString, Chat),
+    /// A private message to a player, sent on behalf of a Discord user
+    Whisper(ValidIGN, CleanString, CleanString),
     /// Mute a player or the guild chat
     Mute(ValidIGN, u8, TimeUnit),
     /// Unmute a player or the guild chat
@@ -45,6 +73,55 @@ pub enum MinecraftCommand {
     Promote(ValidIGN),
     /// Set a player's rank
     SetRank(ValidIGN, CleanString),
+    /// List the online guild members
+    Online,
+    /// List every guild member
+    List,
     /// Execute a command
     Execute(String),
 }
+
+impl MinecraftCommand {
+    pub fn priority(&self) -> Priority {
+        use MinecraftCommand::*;
+
+        match self {
+            ChatMessage(_, _, Chat::Officer) => Priority::Officer,
+            ChatMessage(_, _, Chat::Guild | Chat::Party) | Whisper(..) => Priority::Guild,
+            Mute(..) | Unmute(_) | Invite(_) | Kick(..) | Demote(_) | Promote(_) | SetRank(..) => {
+                Priority::Moderation
+            }
+            Online | List | Execute(_) => Priority::Bulk,
+        }
+    }
+}
+
+impl std::fmt::Display for MinecraftCommand {
+    /// The command as it's typed in Minecraft
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MinecraftCommand::*;
+
+        match self {
+            ChatMessage(author, message, chat) => {
+                write!(f, "/{prefix} {author}: {message}", prefix = chat.prefix())
+            }
+            Whisper(player, author, message) => write!(f, "/msg {player} {author}: {message}"),
+            Mute(player, duration, unit) => {
+                write!(
+                    f,
+                    "/g mute {player} {duration}{unit}",
+                    unit = char::from(*unit)
+                )
+            }
+            Unmute(player) => write!(f, "/g unmute {player}"),
+            Invite(player) => write!(f, "/g invite {player}"),
+            Kick(player, reason) => write!(f, "/g kick {player} {reason}"),
+            Demote(player) => write!(f, "/g demote {player}"),
+            Promote(player) => write!(f, "/g promote {player}"),
+            SetRank(player, rank) => write!(f, "/g setrank {player} {rank}"),
+            Online => write!(f, "/g online"),
+            List => write!(f, "/g list"),
+            Execute(command) => write!(f, "/{command}"),
+        }
+    }
+}
@@ -1,11 +1,21 @@
 mod event;
+mod location;
 mod message;
 mod moderation;
 mod response;
+mod roster;
 mod toggle;
+mod whisper;
 
 pub use {
-    event::GuildEvent, message::Message, moderation::Moderation, response::Response, toggle::Toggle,
+    event::GuildEvent,
+    location::Location,
+    message::Message,
+    moderation::Moderation,
+    response::Response,
+    roster::{GuildOnline, GuildRoster, RankSection},
+    toggle::Toggle,
+    whisper::Whisper,
 };
 
 use azalea::{ecs::prelude::*, prelude::*};
@@ -13,8 +23,10 @@ use std::{fmt::Display, ops::Deref};
 
 #[derive(Debug)]
 pub enum ChatEvent<'a> {
-    /// A message sent to guild/officer chat
+    /// A message sent to guild/officer/party chat
     Message(Message<'a>),
+    /// A private message sent to or by the bot
+    Whisper(Whisper<'a>),
     /// A player joined/left the server
     Toggle(Toggle<'a>),
     /// Player join/leave/kick/promote/demote event
@@ -35,6 +47,10 @@ impl<'a> From<&'a str> for ChatEvent<'a> {
             return ChatEvent::Message(event);
         }
 
+        if let Ok(event) = Whisper::try_from(value) {
+            return ChatEvent::Whisper(event);
+        }
+
         if let Ok(event) = Moderation::try_from(value) {
             return ChatEvent::Moderation(event);
         }
@@ -65,6 +81,7 @@ impl Display for ChatEvent<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ChatEvent::Message(msg) => write!(f, "{}", msg),
+            ChatEvent::Whisper(whisper) => write!(f, "{}", whisper),
             ChatEvent::Toggle(toggle) => write!(f, "{}", toggle),
             ChatEvent::GuildEvent(guild_event) => write!(f, "{}", guild_event),
             ChatEvent::Moderation(moderation) => write!(f, "{}", moderation),
@@ -78,6 +95,14 @@ impl RawChatEvent {
     pub fn as_chat_event(&self) -> ChatEvent {
         self.as_str().into()
     }
+
+    /// Whether the event is a message a player sent, rather than something the server said
+    pub fn is_chat(&self) -> bool {
+        matches!(
+            self.as_chat_event(),
+            ChatEvent::Message(_) | ChatEvent::Whisper(_)
+        )
+    }
 }
 
 #[derive(Event, Debug, Clone)]
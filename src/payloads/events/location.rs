@@ -0,0 +1,76 @@
+use lazy_regex::regex_is_match;
+use std::fmt::Display;
+
+/// Where on the server the bot is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Unknown,
+    Lobby,
+    Limbo,
+}
+
+/// Parse a message announcing the bot has been moved.
+///
+/// # Examples
+/// - `You were spawned in Limbo.`
+/// - `You are AFK. Move around to return from AFK.`
+/// - `A kick occurred in your connection, so you have been routed to limbo!`
+/// - `[MVP+] neyoa joined the lobby!`
+/// - ` >>> [MVP++] neyoa joined the lobby! <<<`
+impl TryFrom<&str> for Location {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "You were spawned in Limbo."
+            || value == "You are AFK. Move around to return from AFK."
+            || value.ends_with("so you have been routed to limbo!")
+        {
+            return Ok(Self::Limbo);
+        }
+
+        // Only ranked players have their arrival announced, so anyone else's name can't be in the message
+        if regex_is_match!(
+            r#"^(?: >>> )?\[[\w+]+\] \w+ joined the lobby!(?: <<<)?$"#,
+            value.trim_end()
+        ) {
+            return Ok(Self::Lobby);
+        }
+
+        Err(())
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Location::Unknown => "Unknown",
+                Location::Lobby => "Lobby",
+                Location::Limbo => "Limbo",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("You were spawned in Limbo.", Location::Limbo ; "Spawned in limbo")]
+    #[test_case("You are AFK. Move around to return from AFK.", Location::Limbo ; "AFK")]
+    #[test_case("A kick occurred in your connection, so you have been routed to limbo!", Location::Limbo ; "Kicked")]
+    #[test_case("[MVP+] neyoa joined the lobby!", Location::Lobby ; "Lobby join")]
+    #[test_case(" >>> [MVP++] neyoa joined the lobby! <<<", Location::Lobby ; "Lobby join announcement")]
+    fn location(input: &str, expected: Location) {
+        assert_eq!(Location::try_from(input), Ok(expected));
+    }
+
+    #[test_case("Guild > neyoa: [MVP+] neytwoa joined the lobby!" ; "Guild chat")]
+    #[test_case("neyoa joined the lobby!" ; "No rank")]
+    fn invalid(input: &str) {
+        assert!(Location::try_from(input).is_err());
+    }
+}
@@ -3,11 +3,12 @@ use azalea::{ecs::prelude::*, prelude::*};
 use lazy_regex::regex_captures;
 use std::fmt::Display;
 
-/// A player sent a message in the guild or officer chat.
+/// A player sent a message in the guild, officer or party chat.
 ///
 /// # Examples
 /// - `Guild > neyoa: hi`
 /// - `Officer > neyoa: hi`
+/// - `Party > neyoa: hi`
 #[derive(Event, Debug)]
 pub struct Message<'a> {
     pub author: &'a str,
@@ -43,6 +44,17 @@ impl<'a> TryFrom<&'a str> for Message<'a> {
             });
         }
 
+        // Party > neyoa: hi
+        if let Some((_, user, message)) =
+            regex_captures!(r#"^Party > (?:\[[\w+]+?\] )?(\w+): (.+)$"#, value)
+        {
+            return Ok(Self {
+                author: user,
+                content: message,
+                chat: Chat::Party,
+            });
+        }
+
         Err(())
     }
 }
@@ -94,4 +106,18 @@ mod tests {
         assert_eq!(content, "Hello, world!");
         assert_eq!(chat, Chat::Officer);
     }
+
+    #[test_case("Party > neyoa: Hello, world!" ; "No Hypixel Rank")]
+    #[test_case("Party > [MVP+] neyoa: Hello, world!" ; "Hypixel Rank")]
+    fn party(input: &'static str) {
+        let Message {
+            author,
+            content,
+            chat,
+        } = input.try_into().unwrap();
+
+        assert_eq!(author, "neyoa");
+        assert_eq!(content, "Hello, world!");
+        assert_eq!(chat, Chat::Party);
+    }
 }
@@ -1,6 +1,6 @@
 use azalea::{ecs::prelude::*, prelude::*};
 use lazy_regex::regex_captures;
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 /// A player or the guild chat was muted or unmuted.
 ///
@@ -121,6 +121,19 @@ impl TryFrom<char> for MuteUnit {
     }
 }
 
+impl MuteUnit {
+    /// How long a mute of `length` of this unit lasts
+    pub fn duration(&self, length: u8) -> Duration {
+        let seconds = match self {
+            Self::Minute => 60,
+            Self::Hour => 60 * 60,
+            Self::Day => 24 * 60 * 60,
+        };
+
+        Duration::from_secs(seconds * u64::from(length))
+    }
+}
+
 impl std::fmt::Display for MuteUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use MuteUnit::*;
@@ -206,4 +219,11 @@ mod tests {
             panic!("Expected GuildUnmute")
         }
     }
+
+    #[test_case(MuteUnit::Minute, 30, 30 * 60 ; "Minutes")]
+    #[test_case(MuteUnit::Hour, 2, 2 * 60 * 60 ; "Hours")]
+    #[test_case(MuteUnit::Day, 30, 30 * 24 * 60 * 60 ; "Days")]
+    fn duration(unit: MuteUnit, length: u8, seconds: u64) {
+        assert_eq!(unit.duration(length), Duration::from_secs(seconds));
+    }
 }
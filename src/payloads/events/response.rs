@@ -9,6 +9,7 @@ pub enum Response<'a> {
     PlayerNotFound(&'a str),
     CommandDisabled,
     BotNotInGuild,
+    TooFast,
 }
 
 const NO_PERMISSION: &[&str] = &[
@@ -46,6 +47,10 @@ impl<'a> TryFrom<&'a str> for Response<'a> {
             return Ok(Self::BotNotInGuild);
         }
 
+        if value.starts_with("You are sending commands too fast!") {
+            return Ok(Self::TooFast);
+        }
+
         Err(())
     }
 }
@@ -58,6 +63,7 @@ impl Display for Response<'_> {
             Self::PlayerNotFound(user) => write!(f, "`{user}` could not be found"),
             Self::CommandDisabled => write!(f, "This command is currently disabled"),
             Self::BotNotInGuild => write!(f, "I'm not in a guild"),
+            Self::TooFast => write!(f, "I'm sending commands too fast"),
         }
     }
 }
@@ -82,4 +88,10 @@ mod tests {
     fn command_disabled(input: &str) {
         assert!(Response::try_from(input).unwrap() == Response::CommandDisabled)
     }
+
+    #[test_case("You are sending commands too fast!" ; "Short")]
+    #[test_case("You are sending commands too fast! Please slow down." ; "Long")]
+    fn too_fast(input: &str) {
+        assert!(Response::try_from(input).unwrap() == Response::TooFast)
+    }
 }
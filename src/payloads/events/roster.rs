@@ -0,0 +1,225 @@
+use lazy_regex::{regex, regex_captures};
+use std::collections::HashMap;
+
+/// The members listed under a guild rank.
+#[derive(Debug, PartialEq)]
+pub struct RankSection<'a> {
+    pub rank: &'a str,
+    pub members: Vec<&'a str>,
+}
+
+/// The response to `/g online`, spanning several lines.
+///
+/// # Examples
+/// ```text
+/// Guild Name: Bridge
+/// -- Guild Master --
+/// [MVP++] neyoa ●
+/// -- Member --
+/// [VIP] neytwoa ●  alpha_bravo ●
+/// Online Members: 3
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct GuildOnline<'a> {
+    pub name: Option<&'a str>,
+    pub ranks: Vec<RankSection<'a>>,
+    pub online: usize,
+}
+
+impl<'a> TryFrom<&'a str> for GuildOnline<'a> {
+    type Error = ();
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let Sections {
+            name,
+            ranks,
+            counts,
+        } = Sections::from(value);
+
+        Ok(Self {
+            name,
+            ranks,
+            online: *counts.get("Online Members").ok_or(())?,
+        })
+    }
+}
+
+/// The response to `/g list`, spanning several lines.
+///
+/// # Examples
+/// ```text
+/// Guild Name: Bridge
+/// -- Guild Master --
+/// [MVP++] neyoa ●
+/// -- Member --
+/// [VIP] neytwoa ●  alpha_bravo ●
+/// Total Members: 3
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct GuildRoster<'a> {
+    pub name: Option<&'a str>,
+    pub ranks: Vec<RankSection<'a>>,
+    pub total: usize,
+}
+
+impl<'a> TryFrom<&'a str> for GuildRoster<'a> {
+    type Error = ();
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let Sections {
+            name,
+            ranks,
+            counts,
+        } = Sections::from(value);
+
+        Ok(Self {
+            name,
+            ranks,
+            total: *counts.get("Total Members").ok_or(())?,
+        })
+    }
+}
+
+impl GuildRoster<'_> {
+    /// Every member in the guild, from the highest rank to the lowest
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.ranks
+            .iter()
+            .flat_map(|section| section.members.iter().copied())
+    }
+}
+
+/// Everything found in a rank-sectioned guild listing
+struct Sections<'a> {
+    name: Option<&'a str>,
+    ranks: Vec<RankSection<'a>>,
+    /// Totals such as `Online Members: 3`, keyed by their label
+    counts: HashMap<&'a str, usize>,
+}
+
+impl<'a> From<&'a str> for Sections<'a> {
+    fn from(value: &'a str) -> Self {
+        let mut sections = Self {
+            name: None,
+            ranks: vec![],
+            counts: HashMap::new(),
+        };
+
+        for line in value.lines().map(str::trim) {
+            // Guild Name: Bridge
+            if let Some((_, name)) = regex_captures!(r#"^Guild Name: (.+)$"#, line) {
+                sections.name = Some(name);
+                continue;
+            }
+
+            // -- Guild Master --
+            if let Some((_, rank)) = regex_captures!(r#"^-- (.+) --$"#, line) {
+                sections.ranks.push(RankSection {
+                    rank,
+                    members: vec![],
+                });
+                continue;
+            }
+
+            // Online Members: 3
+            if let Some((_, label, count)) = regex_captures!(r#"^([\w ]+ Members): (\d+)$"#, line) {
+                if let Ok(count) = count.parse() {
+                    sections.counts.insert(label, count);
+                }
+                continue;
+            }
+
+            // [MVP++] neyoa ●  [VIP] neytwoa ●
+            if let Some(section) = sections.ranks.last_mut() {
+                section.members.extend(
+                    regex!(r#"(?:\[[\w+]+\] )?(\w+) ●"#)
+                        .captures_iter(line)
+                        .filter_map(|captures| captures.get(1))
+                        .map(|member| member.as_str()),
+                );
+            }
+        }
+
+        sections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const ONLINE: &str = "-----------------------------------------------------
+                     Guild Name: Bridge
+
+                         -- Guild Master --
+[MVP++] neyoa ●
+
+                         -- Officer --
+[VIP] neytwoa ●  [MVP+] alpha_bravo ●
+
+                         -- Member --
+charlie ●
+
+Online Members: 4
+-----------------------------------------------------";
+
+    #[test]
+    fn online() {
+        let online = GuildOnline::try_from(ONLINE).unwrap();
+
+        assert_eq!(online.name, Some("Bridge"));
+        assert_eq!(online.online, 4);
+        assert_eq!(
+            online.ranks,
+            vec![
+                RankSection {
+                    rank: "Guild Master",
+                    members: vec!["neyoa"]
+                },
+                RankSection {
+                    rank: "Officer",
+                    members: vec!["neytwoa", "alpha_bravo"]
+                },
+                RankSection {
+                    rank: "Member",
+                    members: vec!["charlie"]
+                },
+            ]
+        );
+    }
+
+    const LIST: &str = "-----------------------------------------------------
+                     Guild Name: Bridge
+
+                         -- Guild Master --
+[MVP++] neyoa ●
+
+                         -- Officer --
+[VIP] neytwoa ●  [MVP+] alpha_bravo ●
+
+                         -- Member --
+charlie ●  delta ●
+
+Total Members: 5
+-----------------------------------------------------";
+
+    #[test]
+    fn list() {
+        let roster = GuildRoster::try_from(LIST).unwrap();
+
+        assert_eq!(roster.name, Some("Bridge"));
+        assert_eq!(roster.total, 5);
+        assert_eq!(
+            roster.members().collect::<Vec<_>>(),
+            vec!["neyoa", "neytwoa", "alpha_bravo", "charlie", "delta"]
+        );
+    }
+
+    #[test_case("You must be in a guild to use this command!" ; "Not in guild")]
+    #[test_case("Guild Name: Bridge\n-- Member --\nneyoa ●" ; "Missing count")]
+    fn invalid(input: &str) {
+        assert!(GuildOnline::try_from(input).is_err());
+        assert!(GuildRoster::try_from(input).is_err());
+    }
+}
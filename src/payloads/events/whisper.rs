@@ -0,0 +1,75 @@
+use azalea::{ecs::prelude::*, prelude::*};
+use lazy_regex::regex_captures;
+use std::fmt::Display;
+
+/// A private message sent to or by the bot.
+///
+/// # Examples
+/// - `From neyoa: hi`
+/// - `To [MVP++] neyoa: hi`
+#[derive(Event, Debug)]
+pub struct Whisper<'a> {
+    /// The player the bot is talking to
+    pub player: &'a str,
+    pub content: &'a str,
+    /// Whether the message was sent to the bot, rather than by it
+    pub incoming: bool,
+}
+
+impl<'a> TryFrom<&'a str> for Whisper<'a> {
+    type Error = ();
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        // From neyoa: hi
+        // To neyoa: hi
+        if let Some((_, direction, player, content)) =
+            regex_captures!(r#"^(From|To) (?:\[[\w+]+?\] )?(\w+): (.+)$"#, value)
+        {
+            return Ok(Self {
+                player,
+                content,
+                incoming: direction == "From",
+            });
+        }
+
+        Err(())
+    }
+}
+
+impl Display for Whisper<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{direction} {player}: {content}",
+            direction = if self.incoming { "From" } else { "To" },
+            player = self.player,
+            content = self.content
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("From neyoa: Hello, world!", true ; "Incoming")]
+    #[test_case("From [MVP++] neyoa: Hello, world!", true ; "Incoming with rank")]
+    #[test_case("To [VIP] neyoa: Hello, world!", false ; "Outgoing")]
+    fn whisper(input: &'static str, expected: bool) {
+        let Whisper {
+            player,
+            content,
+            incoming,
+        } = input.try_into().unwrap();
+
+        assert_eq!(player, "neyoa");
+        assert_eq!(content, "Hello, world!");
+        assert_eq!(incoming, expected);
+    }
+
+    #[test]
+    fn guild_message() {
+        assert!(Whisper::try_from("Guild > neyoa: From neytwoa: hi").is_err());
+    }
+}
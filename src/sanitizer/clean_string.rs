@@ -1,13 +1,13 @@
-use super::chars;
+use super::{chars, transliterate};
 use lazy_regex::regex_replace_all;
 use std::{ops::Deref, sync::Arc};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CleanString(Arc<str>);
 
 impl From<String> for CleanString {
     fn from(input: String) -> Self {
-        let value = regex_replace_all!(r"\n+", input.trim(), |_| " ⤶ ")
+        let value = regex_replace_all!(r"\n+", &transliterate(input.trim()), |_| " ⤶ ")
             .replace(|c| !chars::CHARS.contains(&c), "");
 
         Self(Arc::from(value.trim()))
@@ -0,0 +1,130 @@
+//! Measures text the way Minecraft limits the chat messages and commands it's sent
+
+use std::ops::Add;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The most characters Minecraft accepts, counted in UTF-16 code units as Java counts them
+pub const MAX_LENGTH: usize = 256;
+/// The most bytes Minecraft reads for a message before checking its length
+pub const MAX_BYTES: usize = MAX_LENGTH * 3;
+
+/// How much of the limit some text uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Length {
+    /// UTF-16 code units, so characters outside the Basic Multilingual Plane count twice
+    pub units: usize,
+    /// UTF-8 bytes, as the text is sent
+    pub bytes: usize,
+}
+
+impl Length {
+    pub fn of(text: &str) -> Self {
+        Self {
+            units: text.encode_utf16().count(),
+            bytes: text.len(),
+        }
+    }
+
+    fn fits(self) -> bool {
+        self.units <= MAX_LENGTH && self.bytes <= MAX_BYTES
+    }
+}
+
+impl Add for Length {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            units: self.units + rhs.units,
+            bytes: self.bytes + rhs.bytes,
+        }
+    }
+}
+
+/// Text which doesn't fit in what's left of the limit
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error(
+    "{units}/{MAX_LENGTH} characters and {bytes}/{MAX_BYTES} bytes long",
+    units = .length.units,
+    bytes = .length.bytes
+)]
+pub struct Overflow {
+    /// The length including whatever was already used
+    pub length: Length,
+    /// The end of the longest start of the text which fits, always on a grapheme boundary
+    pub fits: usize,
+}
+
+/// Check `text` fits in the limit after `used`, finding where to cut it short if it doesn't
+///
+/// Text is only cut between grapheme clusters, so accents and emojis are never split
+pub fn fit(text: &str, used: Length) -> Result<(), Overflow> {
+    let length = used + Length::of(text);
+
+    if length.fits() {
+        return Ok(());
+    }
+
+    let mut total = used;
+    let mut fits = 0;
+
+    for (index, grapheme) in text.grapheme_indices(true) {
+        total = total + Length::of(grapheme);
+
+        if !total.fits() {
+            break;
+        }
+
+        fits = index + grapheme.len();
+    }
+
+    Err(Overflow { length, fits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("a", 1, 1 ; "ASCII")]
+    #[test_case("я", 1, 2 ; "Cyrillic")]
+    #[test_case("⚔", 1, 3 ; "Symbol")]
+    #[test_case("🔥", 2, 4 ; "Outside the BMP")]
+    fn length(text: &str, units: usize, bytes: usize) {
+        assert_eq!(Length::of(text), Length { units, bytes });
+    }
+
+    #[test_case(&"a".repeat(256) ; "ASCII")]
+    #[test_case(&"я".repeat(256) ; "Cyrillic")]
+    fn fits(text: &str) {
+        assert_eq!(fit(text, Length::default()), Ok(()));
+    }
+
+    #[test_case(&"я".repeat(300), 256 * 2 ; "Cyrillic")]
+    #[test_case(&"🔥".repeat(200), 128 * 4 ; "Outside the BMP")]
+    #[test_case(&"e\u{301}".repeat(200), 128 * 3 ; "Combining accents")]
+    fn overflow(text: &str, fits: usize) {
+        let overflow = fit(text, Length::default()).unwrap_err();
+
+        assert_eq!(overflow.fits, fits);
+        assert!(Length::of(&text[..overflow.fits]).fits());
+    }
+
+    #[test]
+    fn used() {
+        let overflow = fit("hello", Length::of(&"a".repeat(253))).unwrap_err();
+
+        assert_eq!(overflow.fits, 3);
+        assert_eq!(
+            overflow.length,
+            Length {
+                units: 258,
+                bytes: 258
+            }
+        );
+        assert_eq!(
+            overflow.to_string(),
+            "258/256 characters and 258/768 bytes long"
+        );
+    }
+}
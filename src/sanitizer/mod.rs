@@ -1,6 +1,9 @@
 mod clean_string;
 mod valid_ign;
 mod chars;
+pub mod length;
+mod transliterate;
 
 pub use clean_string::CleanString;
+pub use transliterate::transliterate;
 pub use valid_ign::ValidIGN;
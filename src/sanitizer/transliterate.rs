@@ -0,0 +1,58 @@
+//! Rewrites text Minecraft can't show into text it can, before anything left over is removed
+
+use super::chars;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Asks for an emoji to be shown as one, and means nothing on its own
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+
+/// Write emojis as their `:shortcode:`, and fold styled and full-width letters to plain ones
+///
+/// Each grapheme cluster is handled as a whole, so emojis built from several characters
+/// (skin tones, flags, families) become one shortcode rather than several pieces
+pub fn transliterate(input: &str) -> String {
+    input.graphemes(true).map(grapheme).collect()
+}
+
+fn grapheme(grapheme: &str) -> String {
+    let plain = grapheme.replace(VARIATION_SELECTOR, "");
+
+    if !plain.is_empty() && plain.chars().all(|c| chars::CHARS.contains(&c)) {
+        return plain;
+    }
+
+    if let Some(shortcode) = shortcode(grapheme).or_else(|| shortcode(&plain)) {
+        return format!(":{shortcode}:");
+    }
+
+    // Compatibility forms such as 𝐛𝐨𝐥𝐝, 𝓼𝓬𝓻𝓲𝓹𝓽 and ｆｕｌｌ-ｗｉｄｔｈ letters normalise to plain ones
+    grapheme.nfkc().collect()
+}
+
+fn shortcode(grapheme: &str) -> Option<&'static str> {
+    let emoji = emojis::get(grapheme)?;
+
+    // Only the default skin tone has shortcodes
+    emoji
+        .shortcode()
+        .or_else(|| emoji.with_skin_tone(emojis::SkinTone::Default)?.shortcode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("😀", ":grinning:" ; "Emoji")]
+    #[test_case("Hello 😀!", "Hello :grinning:!" ; "Emoji in text")]
+    #[test_case("👋🏽", ":wave:" ; "Skin tone")]
+    #[test_case("❤️", "❤" ; "Shown in game")]
+    #[test_case("𝐇𝐞𝐥𝐥𝐨 𝓌𝑜𝓇𝓁𝒹", "Hello world" ; "Styled letters")]
+    #[test_case("ｆｕｌｌ ｗｉｄｔｈ", "full width" ; "Full width")]
+    #[test_case("e\u{301}", "é" ; "Combining accent")]
+    #[test_case("Привет, neyoa", "Привет, neyoa" ; "Unchanged")]
+    fn transliterated(input: &str, expected: &str) {
+        assert_eq!(transliterate(input), expected);
+    }
+}
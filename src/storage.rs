@@ -0,0 +1,332 @@
+pub mod history;
+mod migrations;
+
+use crate::config;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::sync::Notify;
+
+/// The file everything is stored in, inside the data directory
+const FILE_NAME: &str = "store.toml";
+/// How often changes which aren't saved straight away, such as counters, are saved
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+static STORE: OnceCell<Mutex<Store>> = OnceCell::new();
+/// Woken whenever something which should be saved straight away changes
+static SAVE: Lazy<Notify> = Lazy::new(Notify::new);
+/// Only one save runs at a time, so an older copy of the store can't replace a newer one
+static SAVING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+struct Store {
+    /// The directory the store is loaded from and saved to
+    dir: PathBuf,
+    data: Data,
+    /// Whether the data changed since it was last saved
+    dirty: bool,
+}
+
+/// Everything the bridge remembers between restarts.
+///
+/// Changing this means adding a migration, so existing stores can still be read
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct Data {
+    /// Guild members suggested by autocomplete
+    pub usernames: BTreeSet<String>,
+    /// The IGN each Discord user has linked, keyed by user ID
+    pub links: BTreeMap<String, String>,
+    /// The webhook used to send messages in each channel, keyed by channel ID
+    pub webhooks: BTreeMap<String, StoredWebhook>,
+    /// When each muted player's mute ends as a Unix timestamp, keyed by lowercase IGN
+    pub mutes: BTreeMap<String, u64>,
+    /// Running totals, keyed by [`Counter::key`]
+    pub counters: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredWebhook {
+    pub id: u64,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Counter {
+    /// Messages sent from Minecraft to Discord
+    FromMinecraft,
+    /// Messages sent from Discord to Minecraft
+    FromDiscord,
+}
+
+impl Counter {
+    fn key(&self) -> &'static str {
+        match self {
+            Counter::FromMinecraft => "from_minecraft",
+            Counter::FromDiscord => "from_discord",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Couldn't load {path}: {reason}")]
+pub struct LoadError {
+    path: String,
+    reason: String,
+}
+
+impl Store {
+    /// Load the store in `dir`
+    fn open(dir: &Path) -> Result<Self, LoadError> {
+        let (data, migrated) = load(dir)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            data,
+            // An upgraded store is saved in the new format as soon as it can be
+            dirty: migrated,
+        })
+    }
+}
+
+/// Load the store from the data directory, which has to happen before anything reads or writes it
+pub fn init() -> Result<(), LoadError> {
+    let store = Store::open(&config().data_dir)?;
+
+    assert!(STORE.set(Mutex::new(store)).is_ok(), "Store already loaded");
+    SAVE.notify_one();
+
+    Ok(())
+}
+
+/// Load an empty store from the test data directory, for tests which need it
+#[cfg(test)]
+pub fn init_for_tests() {
+    crate::config::init_for_tests();
+
+    STORE.get_or_init(|| {
+        let dir = &config().data_dir;
+        // Each run starts from nothing, rather than whatever an earlier run left behind
+        std::fs::remove_dir_all(dir).ok();
+
+        Mutex::new(Store::open(dir).expect("Test store is invalid"))
+    });
+}
+
+fn store() -> &'static Mutex<Store> {
+    STORE.get().expect("Store not loaded")
+}
+
+/// Read from the store
+pub fn read<T>(f: impl FnOnce(&Data) -> T) -> T {
+    f(&store().lock().data)
+}
+
+/// Change the store, saving it shortly afterwards
+pub fn write<T>(f: impl FnOnce(&mut Data) -> T) -> T {
+    let result = change(f);
+    SAVE.notify_one();
+
+    result
+}
+
+/// Change the store, leaving it to be saved with the next change or flush
+fn change<T>(f: impl FnOnce(&mut Data) -> T) -> T {
+    let mut store = store().lock();
+    store.dirty = true;
+
+    f(&mut store.data)
+}
+
+/// Count a message, which only reaches the file with the next periodic flush since it happens so often
+pub fn increment(counter: Counter) {
+    change(|data| *data.counters.entry(counter.key().to_string()).or_default() += 1);
+}
+
+pub fn count(counter: Counter) -> u64 {
+    read(|data| {
+        data.counters
+            .get(counter.key())
+            .copied()
+            .unwrap_or_default()
+    })
+}
+
+/// Remember that `ign` is muted for `length`
+pub fn mute(ign: &str, length: Duration) {
+    let ends = (now() + length).as_secs();
+
+    write(|data| data.mutes.insert(ign.to_lowercase(), ends));
+}
+
+pub fn unmute(ign: &str) {
+    write(|data| data.mutes.remove(&ign.to_lowercase()));
+}
+
+/// Whether `ign` is muted and their mute hasn't ended yet
+pub fn is_muted(ign: &str) -> bool {
+    let now = now().as_secs();
+
+    read(|data| {
+        data.mutes
+            .get(&ign.to_lowercase())
+            .is_some_and(|ends| *ends > now)
+    })
+}
+
+/// The time since the Unix epoch
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("The system clock is before 1970")
+}
+
+/// Save the store whenever something changes, and every [`FLUSH_INTERVAL`] for changes which can wait
+pub async fn run() {
+    loop {
+        tokio::select! {
+            _ = SAVE.notified() => {}
+            _ = tokio::time::sleep(FLUSH_INTERVAL) => {}
+        }
+
+        flush().await;
+    }
+}
+
+/// Save the store if it changed since it was last saved
+pub async fn flush() {
+    let _saving = SAVING.lock().await;
+
+    let (dir, contents) = {
+        let mut store = store().lock();
+
+        if !std::mem::take(&mut store.dirty) {
+            return;
+        }
+
+        (store.dir.clone(), serialise(&store.data))
+    };
+
+    if let Err(e) = tokio::task::spawn_blocking(move || save(&dir, &contents)).await {
+        tracing::error!("Couldn't save the store: {e}");
+    }
+}
+
+/// Read the store in `data_dir`, upgrading it if it was saved by an older version.
+/// Returns whether it was upgraded, and so needs saving
+fn load(data_dir: &Path) -> Result<(Data, bool), LoadError> {
+    let path = data_dir.join(FILE_NAME);
+    // Starting again would overwrite everything, so a store which can't be read has to be fixed by hand
+    let error = |reason: String| LoadError {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let mut table = match std::fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents).map_err(|e| error(e.to_string()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(error(e.to_string())),
+    };
+
+    let migrated = migrations::run(&mut table, data_dir).map_err(error)?;
+    let data = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| error(e.to_string()))?;
+
+    Ok((data, migrated))
+}
+
+fn serialise(data: &Data) -> String {
+    let mut table = toml::Table::try_from(data).expect("The store should always serialise");
+    table.insert("version".to_string(), migrations::VERSION.into());
+
+    table.to_string()
+}
+
+fn save(data_dir: &Path, contents: &str) {
+    let path = data_dir.join(FILE_NAME);
+
+    // Write to a temporary file first, so the store isn't lost if the bridge stops halfway through
+    let temporary = path.with_extension("toml.tmp");
+    let result = std::fs::create_dir_all(data_dir)
+        .and_then(|_| std::fs::write(&temporary, contents))
+        .and_then(|_| std::fs::rename(&temporary, &path));
+
+    if let Err(e) = result {
+        tracing::error!("Couldn't save {path}: {e}", path = path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = Data {
+            usernames: BTreeSet::from(["neyoa".to_string()]),
+            links: BTreeMap::from([("1234".to_string(), "neyoa".to_string())]),
+            webhooks: BTreeMap::from([(
+                "5678".to_string(),
+                StoredWebhook {
+                    id: 1,
+                    token: "token".to_string(),
+                },
+            )]),
+            mutes: BTreeMap::from([("neytwoa".to_string(), 1_700_000_000)]),
+            counters: BTreeMap::from([(Counter::FromDiscord.key().to_string(), 3)]),
+        };
+
+        let table = toml::Table::try_from(&data).unwrap();
+
+        assert_eq!(toml::Value::Table(table).try_into::<Data>().unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn flush() {
+        init_for_tests();
+        increment(Counter::FromMinecraft);
+        assert!(store().lock().dirty);
+
+        super::flush().await;
+        let (saved, _) = load(&config().data_dir).unwrap();
+
+        assert!(count(Counter::FromMinecraft) >= 1);
+        assert!(saved.counters[Counter::FromMinecraft.key()] >= 1);
+    }
+
+    #[test]
+    fn upgraded() {
+        let dir = std::env::temp_dir().join("bridge-store-test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(FILE_NAME), "usernames = [\"neyoa\"]\n").unwrap();
+
+        let store = Store::open(&dir).unwrap();
+        assert!(store.dirty);
+
+        save(&store.dir, &serialise(&store.data));
+        let reopened = Store::open(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!reopened.dirty);
+        assert_eq!(reopened.data, store.data);
+        assert!(reopened.data.usernames.contains("neyoa"));
+    }
+
+    #[test]
+    fn unreadable() {
+        let dir = std::env::temp_dir().join("bridge-store-invalid-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(FILE_NAME), "usernames = ").unwrap();
+
+        let result = Store::open(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+    }
+}
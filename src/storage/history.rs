@@ -0,0 +1,390 @@
+//! Every message bridged in either direction, kept in its own append-only file
+//! since it only ever grows and would be slow to rewrite with the rest of the store.
+//!
+//! Once the file reaches [`MAX_FILE_SIZE`] it replaces the previous one, so at most two files are kept
+
+use super::now;
+use crate::{bridge::Chat, config};
+use parking_lot::Mutex;
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// The file the history is kept in, inside the data directory
+const FILE_NAME: &str = "history.log";
+/// The file the history is moved to once it's full, replacing the one before it
+const OLD_FILE_NAME: &str = "history.old.log";
+/// How large the history file grows before it's moved aside
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// How much of the file is read at once while searching backwards through it
+const CHUNK_SIZE: u64 = 64 * 1024;
+/// The most results a search returns, newest first
+pub const MAX_RESULTS: usize = 500;
+
+/// Only one message is appended at a time, so lines can't be interleaved
+/// and a search never starts reading halfway through one
+static FILE: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// When the message was bridged, as a Unix timestamp
+    pub time: u64,
+    pub chat: Chat,
+    /// Whether the message was sent from Discord, rather than Minecraft
+    pub from_discord: bool,
+    pub author: String,
+    pub content: String,
+}
+
+/// What to look for in the history
+pub struct Query<'a> {
+    /// Text the message contains, ignoring case
+    pub text: &'a str,
+    pub player: Option<&'a str>,
+    /// The chats to look in, leaving out any the searcher can't see
+    pub chats: &'a [Chat],
+    /// Only messages bridged at most this long ago
+    pub since: Option<Duration>,
+}
+
+impl Entry {
+    fn matches(&self, query: &Query, now: Duration) -> bool {
+        self.content
+            .to_lowercase()
+            .contains(&query.text.to_lowercase())
+            && query.chats.contains(&self.chat)
+            && query
+                .player
+                .is_none_or(|player| self.author.eq_ignore_ascii_case(player))
+            && query
+                .since
+                .is_none_or(|since| self.time >= now.saturating_sub(since).as_secs())
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{time}\t{chat}\t{direction}\t{author}\t{content}",
+            time = self.time,
+            chat = self.chat.prefix(),
+            direction = if self.from_discord { 'd' } else { 'm' },
+            author = escape(&self.author),
+            content = escape(&self.content)
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, '\t');
+
+        Some(Self {
+            time: fields.next()?.parse().ok()?,
+            chat: match fields.next()? {
+                "gc" => Chat::Guild,
+                "oc" => Chat::Officer,
+                "pc" => Chat::Party,
+                _ => return None,
+            },
+            from_discord: match fields.next()? {
+                "d" => true,
+                "m" => false,
+                _ => return None,
+            },
+            author: unescape(fields.next()?),
+            content: unescape(fields.next()?),
+        })
+    }
+}
+
+/// Add a message to the history
+pub fn record(chat: Chat, from_discord: bool, author: &str, content: &str) {
+    let entry = Entry {
+        time: now().as_secs(),
+        chat,
+        from_discord,
+        author: author.to_string(),
+        content: content.to_string(),
+    };
+
+    append(&config().data_dir, &entry);
+}
+
+/// Add an entry to the history in `data_dir`, moving the file aside once it's full
+fn append(data_dir: &Path, entry: &Entry) {
+    let path = data_dir.join(FILE_NAME);
+    let _lock = FILE.lock();
+
+    let result = std::fs::create_dir_all(data_dir).and_then(|_| {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{line}", line = entry.to_line())?;
+
+        if file.metadata()?.len() >= MAX_FILE_SIZE {
+            std::fs::rename(&path, data_dir.join(OLD_FILE_NAME))?;
+        }
+
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        tracing::error!("Couldn't save {path}: {e}", path = path.display());
+    }
+}
+
+/// Find the messages matching `query`, newest first.
+///
+/// This reads files, so it should be run somewhere it can block
+pub fn search(query: &Query) -> Vec<Entry> {
+    search_in(&config().data_dir, query, now())
+}
+
+fn search_in(data_dir: &Path, query: &Query, now: Duration) -> Vec<Entry> {
+    [FILE_NAME, OLD_FILE_NAME]
+        .into_iter()
+        .flat_map(|name| newest_first(&data_dir.join(name)))
+        .filter_map(|line| Entry::from_line(&line))
+        // Entries are in the order they were bridged, so nothing further back is recent enough
+        .take_while(|entry| {
+            query
+                .since
+                .is_none_or(|since| entry.time >= now.saturating_sub(since).as_secs())
+        })
+        .filter(|entry| entry.matches(query, now))
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+/// The lines of the file at `path`, starting from the end, which are read as they're needed
+fn newest_first(path: &Path) -> impl Iterator<Item = String> {
+    let mut file = File::open(path).ok();
+    let mut end = {
+        // Lines are only appended whole while the lock is held
+        let _lock = FILE.lock();
+        file.as_ref()
+            .and_then(|file| file.metadata().ok())
+            .map_or(0, |metadata| metadata.len())
+    };
+    // Bytes read before the start of the line being read, newest last
+    let mut buffer = Vec::new();
+
+    std::iter::from_fn(move || loop {
+        // The newest whole line in the buffer, after its last newline but one
+        let trimmed = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        if let Some(start) = trimmed.iter().rposition(|byte| *byte == b'\n') {
+            let line = String::from_utf8_lossy(&trimmed[start + 1..]).into_owned();
+            buffer.truncate(start + 1);
+            return Some(line);
+        }
+
+        if end == 0 {
+            let line = (!trimmed.is_empty()).then(|| String::from_utf8_lossy(trimmed).into_owned());
+            buffer.clear();
+            return line;
+        }
+
+        let start = end.saturating_sub(CHUNK_SIZE);
+        let mut chunk = vec![0; (end - start) as usize];
+        let read = file.as_mut().is_some_and(|file| {
+            file.seek(SeekFrom::Start(start))
+                .and_then(|_| file.read_exact(&mut chunk))
+                .is_ok()
+        });
+
+        if !read {
+            return None;
+        }
+
+        chunk.append(&mut buffer);
+        buffer = chunk;
+        end = start;
+    })
+}
+
+/// Keep each entry on one line, with tabs only between fields
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn entry(author: &str, content: &str) -> Entry {
+        Entry {
+            time: 1_700_000_000,
+            chat: Chat::Officer,
+            from_discord: true,
+            author: author.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test_case("hello" ; "Plain")]
+    #[test_case("tab\there" ; "Tab")]
+    #[test_case("line\nbreak \\n" ; "Newline and backslash")]
+    fn round_trip(content: &str) {
+        let entry = entry("neyoa", content);
+        let line = entry.to_line();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(Entry::from_line(&line), Some(entry));
+    }
+
+    #[test_case("", None, None, true ; "Everything")]
+    #[test_case("HELLO", None, None, true ; "Text ignores case")]
+    #[test_case("bye", None, None, false ; "Wrong text")]
+    #[test_case("", Some("NEYOA"), None, true ; "Player ignores case")]
+    #[test_case("", Some("neytwoa"), None, false ; "Wrong player")]
+    #[test_case("", None, Some(60), true ; "Since")]
+    #[test_case("", None, Some(59), false ; "Too old")]
+    fn matches(text: &str, player: Option<&str>, since: Option<u64>, expected: bool) {
+        let query = Query {
+            text,
+            player,
+            chats: &[Chat::Guild, Chat::Officer],
+            since: since.map(Duration::from_secs),
+        };
+        let now = Duration::from_secs(1_700_000_060);
+
+        assert_eq!(entry("neyoa", "hello there").matches(&query, now), expected);
+    }
+
+    #[test_case(&[Chat::Officer], true ; "Officer")]
+    #[test_case(&[Chat::Guild, Chat::Party], false ; "Officer left out")]
+    #[test_case(&[], false ; "No chats")]
+    fn chats(chats: &[Chat], expected: bool) {
+        let query = Query {
+            text: "",
+            player: None,
+            chats,
+            since: None,
+        };
+
+        assert_eq!(
+            entry("neyoa", "hello").matches(&query, Duration::from_secs(1_700_000_060)),
+            expected
+        );
+    }
+
+    #[test]
+    fn search() {
+        let data_dir = std::env::temp_dir().join("bridge-history-search-test");
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        for (time, chat, content) in [
+            (100, Chat::Officer, "hello old"),
+            (200, Chat::Officer, "goodbye"),
+            (300, Chat::Officer, "hello new"),
+            (400, Chat::Guild, "hello guild"),
+        ] {
+            append(
+                &data_dir,
+                &Entry {
+                    time,
+                    chat,
+                    ..entry("neyoa", content)
+                },
+            );
+        }
+
+        let query = Query {
+            text: "hello",
+            player: None,
+            chats: &[Chat::Officer],
+            since: Some(Duration::from_secs(250)),
+        };
+        let recent = search_in(&data_dir, &query, Duration::from_secs(500));
+        let all = search_in(
+            &data_dir,
+            &Query {
+                since: None,
+                ..query
+            },
+            Duration::from_secs(500),
+        );
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            recent
+                .iter()
+                .map(|entry| entry.content.as_str())
+                .collect::<Vec<_>>(),
+            ["hello new"]
+        );
+        assert_eq!(
+            all.iter()
+                .map(|entry| entry.content.as_str())
+                .collect::<Vec<_>>(),
+            ["hello new", "hello old"]
+        );
+    }
+
+    #[test]
+    fn rotated() {
+        let data_dir = std::env::temp_dir().join("bridge-history-rotate-test");
+        std::fs::remove_dir_all(&data_dir).ok();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        File::create(data_dir.join(FILE_NAME))
+            .and_then(|file| file.set_len(MAX_FILE_SIZE - 1))
+            .unwrap();
+
+        append(&data_dir, &entry("neyoa", "fills the file"));
+        let moved = data_dir.join(FILE_NAME).exists();
+        append(&data_dir, &entry("neyoa", "starts a new one"));
+
+        let old = std::fs::metadata(data_dir.join(OLD_FILE_NAME))
+            .unwrap()
+            .len();
+        let new = std::fs::read_to_string(data_dir.join(FILE_NAME)).unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(!moved);
+        assert!(old >= MAX_FILE_SIZE);
+        assert_eq!(new, entry("neyoa", "starts a new one").to_line() + "\n");
+    }
+
+    #[test]
+    fn newest_first() {
+        let path = std::env::temp_dir().join("bridge-history-test.log");
+        let lines = (0..20_000).map(|n| format!("line {n}")).collect::<Vec<_>>();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let read = super::newest_first(&path).collect::<Vec<_>>();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), lines.len());
+        assert!(read.iter().eq(lines.iter().rev()));
+        assert_eq!(super::newest_first(&path).count(), 0);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Entry::from_line("not an entry"), None);
+        assert_eq!(Entry::from_line("1\txx\td\tneyoa\thi"), None);
+    }
+}
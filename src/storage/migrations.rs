@@ -0,0 +1,93 @@
+use std::path::Path;
+use toml::Table;
+
+/// Each migration upgrades the store from the version before it.
+/// Never change a migration once it's released, add another one instead
+const MIGRATIONS: &[fn(&mut Table, &Path)] = &[import_links];
+
+/// The version of stores saved by this build of the bridge
+pub const VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Upgrade the store to the current version, returning whether anything changed,
+/// or an error if the store was saved by a newer version of the bridge
+pub fn run(table: &mut Table, data_dir: &Path) -> Result<bool, String> {
+    let version = table
+        .remove("version")
+        .and_then(|version| version.as_integer())
+        .unwrap_or(0);
+
+    if version > VERSION {
+        return Err(format!(
+            "the store is from a newer version of the bridge (version {version}, expected at most {VERSION})"
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        tracing::info!("Upgrading the store from version {from}");
+        migration(table, data_dir);
+    }
+
+    Ok(version < VERSION)
+}
+
+/// Version 1: links used to be kept in their own file
+fn import_links(table: &mut Table, data_dir: &Path) {
+    let path = data_dir.join("links.toml");
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return;
+    };
+
+    match toml::from_str::<Table>(&contents) {
+        Ok(mut file) => {
+            if let Some(links) = file.remove("links") {
+                table.insert("links".to_string(), links);
+            }
+
+            tracing::info!(
+                "Imported links from {path}, it can now be deleted",
+                path = path.display()
+            );
+        }
+        Err(e) => tracing::error!("Couldn't import {path}: {e}", path = path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml::Value;
+
+    #[test]
+    fn links() {
+        let data_dir = std::env::temp_dir().join("bridge-migration-test");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(
+            data_dir.join("links.toml"),
+            "[links]\n\"1234\" = \"neyoa\"\n",
+        )
+        .unwrap();
+
+        let mut table = Table::new();
+        assert_eq!(run(&mut table, &data_dir), Ok(true));
+        assert_eq!(table["links"]["1234"], Value::String("neyoa".to_string()));
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn up_to_date() {
+        let mut table = Table::new();
+        table.insert("version".to_string(), VERSION.into());
+
+        assert_eq!(run(&mut table, Path::new("missing")), Ok(false));
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn newer() {
+        let mut table = Table::new();
+        table.insert("version".to_string(), (VERSION + 1).into());
+
+        assert!(run(&mut table, Path::new("missing")).is_err());
+    }
+}